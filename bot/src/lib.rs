@@ -0,0 +1,105 @@
+#![no_std]
+use soroban_sdk::{contract, contractimpl, vec, Bytes, Env, Vec};
+
+// Same 2 bits per cell grid as the game contract, 0b01 is X and 0b10 is O
+const LINES: [u32; 8] = [
+    0b010101,
+    0b010101000000,
+    0b010101000000000000,
+    0b010000010000010000,
+    0b000100000100000100,
+    0b000001000001000001,
+    0b010000000100000001,
+    0b000001000100010000,
+];
+
+#[contract]
+pub struct BotContract;
+
+#[contractimpl]
+impl BotContract {
+    // Returns the cell (pos_y * 3 + pos_x) where `mark` should play.
+    // `level` is the chance, from 0 to 100, of playing the perfect move
+    // instead of a random one.
+    pub fn best_move(env: Env, grid: u32, mark: u32, level: u32) -> u32 {
+        assert!(mark == 0b01 || mark == 0b10, "Invalid mark");
+        assert!(line_winner(grid) == 0, "Game is already won");
+        let empty = empty_cells(&env, grid);
+        assert!(!empty.is_empty(), "Grid is full");
+
+        if level < 100 && draw(&env, grid, 0, 100) >= level {
+            let index = draw(&env, grid, 1, empty.len());
+            return empty.get(index).unwrap();
+        }
+
+        // A win now beats one the search would find later, and blocking a win
+        // is forced, even in a lost game where every move scores the same
+        for target in [mark, mark ^ 0b11] {
+            for cell in empty.iter() {
+                if line_winner(grid | target << (cell * 2)) == target {
+                    return cell;
+                }
+            }
+        }
+
+        let mut best_cell = empty.get(0).unwrap();
+        let mut best_score = -2;
+        for cell in solver::ORDER {
+            if (grid >> (cell * 2)) & 0b11 != 0 {
+                continue;
+            }
+            // Only whether the move beats `best_score` matters, so the window stops there
+            let child = grid | mark << (cell * 2);
+            let score = -solver::score(child, mark ^ 0b11, -1, -best_score);
+            if score > best_score {
+                best_score = score;
+                best_cell = cell;
+            }
+            if best_score == 1 {
+                break;
+            }
+        }
+        best_cell
+    }
+}
+
+// The host has no random generator, so draws hash the ledger with the grid.
+// Anyone watching the ledger can predict them, which is fine for a casual bot.
+fn draw(env: &Env, grid: u32, round: u8, range: u32) -> u32 {
+    let mut seed = Bytes::new(env);
+    seed.extend_from_array(&env.ledger().sequence().to_be_bytes());
+    seed.extend_from_array(&env.ledger().timestamp().to_be_bytes());
+    seed.extend_from_array(&grid.to_be_bytes());
+    seed.push_back(round);
+    let hash = env.crypto().sha256(&seed).to_array();
+    u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]) % range
+}
+
+fn line_winner(grid: u32) -> u32 {
+    for line in LINES {
+        if grid & line == line {
+            return 0b01;
+        }
+        if grid & (line << 1) == line << 1 {
+            return 0b10;
+        }
+    }
+    0
+}
+
+fn is_full(grid: u32) -> bool {
+    (0..9).all(|cell| (grid >> (cell * 2)) & 0b11 != 0)
+}
+
+fn empty_cells(env: &Env, grid: u32) -> Vec<u32> {
+    let mut cells = vec![env];
+    for cell in 0..9 {
+        if (grid >> (cell * 2)) & 0b11 == 0 {
+            cells.push_back(cell);
+        }
+    }
+    cells
+}
+
+mod solver;
+mod test;
@@ -0,0 +1,124 @@
+use crate::{is_full, line_winner};
+use core::cmp::min;
+
+// The same book and search as the game's `analyze`. Searching the whole game
+// from an early position costs more than a transaction's budget.
+
+// The result for the player about to play `mark`, from the book when the
+// position is in it
+pub fn score(grid: u32, mark: u32, alpha: i32, beta: i32) -> i32 {
+    match book_score(grid, mark) {
+        Some(score) => score,
+        None => negamax(grid, mark, alpha, beta),
+    }
+}
+
+// Mask of the lower bit of every cell
+const CELLS: u32 = 0b010101010101010101;
+
+// Marks up to which positions are in the book, deeper ones take at most a few
+// hundred nodes to search
+const BOOK_MARKS: u32 = 4;
+
+// Where each cell goes under the 7 other rotations and reflections of the grid
+const SYMMETRIES: [[u32; 9]; 7] = [
+    [2, 1, 0, 5, 4, 3, 8, 7, 6],
+    [2, 5, 8, 1, 4, 7, 0, 3, 6],
+    [0, 3, 6, 1, 4, 7, 2, 5, 8],
+    [8, 7, 6, 5, 4, 3, 2, 1, 0],
+    [6, 7, 8, 3, 4, 5, 0, 1, 2],
+    [6, 3, 0, 7, 4, 1, 8, 5, 2],
+    [8, 5, 2, 7, 4, 1, 6, 3, 0],
+];
+
+// Every position with up to `BOOK_MARKS` marks, seen by the player on turn as
+// X, in its smallest symmetric form, as `grid << 2 | (score + 1)`. Sorted, it
+// was generated by searching each position, `test_book` checks it.
+#[rustfmt::skip]
+const BOOK: [u32; 162] = [
+    0x000001, 0x000009, 0x000019, 0x000021, 0x000026, 0x00004a,
+    0x00006a, 0x000099, 0x000122, 0x000128, 0x00016a, 0x000182,
+    0x000188, 0x00019a, 0x0001a2, 0x0001a6, 0x000225, 0x000242,
+    0x000248, 0x000258, 0x000262, 0x000266, 0x000285, 0x000291,
+    0x000296, 0x000409, 0x000422, 0x000429, 0x00046a, 0x000489,
+    0x00049a, 0x00052a, 0x00058a, 0x0005a2, 0x000621, 0x000626,
+    0x00064a, 0x000662, 0x000681, 0x000686, 0x000692, 0x000801,
+    0x000805, 0x000811, 0x000818, 0x000825, 0x000849, 0x000859,
+    0x000865, 0x000919, 0x000920, 0x000926, 0x000949, 0x000961,
+    0x000980, 0x000986, 0x000991, 0x000a41, 0x000a46, 0x000a52,
+    0x00112a, 0x00118a, 0x001201, 0x001209, 0x00121a, 0x001221,
+    0x001226, 0x00124a, 0x001262, 0x001281, 0x001285, 0x001291,
+    0x001609, 0x001622, 0x001681, 0x001908, 0x001920, 0x001a01,
+    0x001a06, 0x001a12, 0x001a42, 0x002206, 0x002212, 0x002216,
+    0x002246, 0x002602, 0x002606, 0x002612, 0x00406a, 0x004082,
+    0x004088, 0x00409a, 0x0040a2, 0x0040a6, 0x004188, 0x0041a2,
+    0x004262, 0x004281, 0x004286, 0x004292, 0x004489, 0x0044a2,
+    0x004682, 0x00484a, 0x004861, 0x004881, 0x004886, 0x004891,
+    0x004982, 0x00502a, 0x005089, 0x0050a2, 0x00520a, 0x005222,
+    0x005281, 0x00580a, 0x005821, 0x006009, 0x00601a, 0x006022,
+    0x006026, 0x00604a, 0x006062, 0x006086, 0x006109, 0x006122,
+    0x006182, 0x006206, 0x006212, 0x006242, 0x00640a, 0x006422,
+    0x006806, 0x008084, 0x008091, 0x008096, 0x008192, 0x008481,
+    0x008486, 0x008492, 0x009008, 0x009018, 0x009021, 0x009026,
+    0x00904a, 0x009062, 0x009086, 0x009092, 0x00910a, 0x009122,
+    0x009182, 0x009212, 0x00940a, 0x009422, 0x009812, 0x00a016,
+    0x00a046, 0x00a111, 0x011222, 0x012212, 0x04408a, 0x048086,
+];
+
+pub fn book_score(grid: u32, mark: u32) -> Option<i32> {
+    if ((grid | grid >> 1) & CELLS).count_ones() > BOOK_MARKS {
+        return None;
+    }
+    // Positions no game can reach, e.g. from a channel state, are searched
+    let key = book_key(grid, mark);
+    let index = BOOK.binary_search_by_key(&key, |entry| entry >> 2).ok()?;
+    Some((BOOK[index] & 0b11) as i32 - 1)
+}
+
+// The position as the player on turn playing X sees it, in its smallest symmetric form
+fn book_key(grid: u32, mark: u32) -> u32 {
+    let grid = match mark {
+        0b01 => grid,
+        _ => ((grid & CELLS) << 1) | ((grid >> 1) & CELLS),
+    };
+    let mut key = grid;
+    for symmetry in SYMMETRIES {
+        let mut moved = 0;
+        for cell in 0..9 {
+            moved |= ((grid >> (cell * 2)) & 0b11) << (symmetry[cell as usize] * 2);
+        }
+        key = min(key, moved);
+    }
+    key
+}
+
+// Cells in the order they are searched, the centre and corners cut off more of the search
+pub const ORDER: [u32; 9] = [4, 0, 2, 6, 8, 1, 3, 5, 7];
+
+// 1 if the player about to play `mark` wins, -1 if they lose and 0 for a draw
+pub fn negamax(grid: u32, mark: u32, mut alpha: i32, beta: i32) -> i32 {
+    if line_winner(grid) == mark ^ 0b11 {
+        return -1;
+    }
+    if is_full(grid) {
+        return 0;
+    }
+
+    let mut best = -1;
+    for cell in ORDER {
+        if (grid >> (cell * 2)) & 0b11 != 0 {
+            continue;
+        }
+        let score = -negamax(grid | mark << (cell * 2), mark ^ 0b11, -beta, -alpha);
+        if score > best {
+            best = score;
+        }
+        if best > alpha {
+            alpha = best;
+        }
+        if alpha >= beta {
+            break;
+        }
+    }
+    best
+}
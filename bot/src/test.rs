@@ -0,0 +1,106 @@
+#![cfg(test)]
+
+use super::{solver, BotContract, BotContractClient};
+extern crate std;
+use soroban_sdk::Env;
+
+// The built contract, only the host calls of the native one are metered
+mod wasm_bot {
+    soroban_sdk::contractimport!(file = "../game/tictactoe_bot.wasm");
+}
+
+fn setup<'a>(env: &Env) -> BotContractClient<'a> {
+    env.budget().reset_unlimited();
+    BotContractClient::new(env, &env.register_contract(None, BotContract))
+}
+
+#[test]
+fn test_takes_win() {
+    let env = Env::default();
+    let client = setup(&env);
+
+    // O on cells 0 and 1, X on cells 3, 4 and 8
+    let grid = 0b010000000101001010;
+
+    assert_eq!(client.best_move(&grid, &0b10, &100), 2);
+}
+
+#[test]
+fn test_blocks_win() {
+    let env = Env::default();
+    let client = setup(&env);
+
+    // X on cells 0 and 4, O on cell 1
+    let grid = 0b000000000100001001;
+
+    assert_eq!(client.best_move(&grid, &0b10, &100), 8);
+}
+
+#[test]
+fn test_answers_corner_with_centre() {
+    let env = Env::default();
+    let client = setup(&env);
+
+    // X on cell 0, any other answer loses
+    assert_eq!(client.best_move(&0b01, &0b10, &100), 4);
+}
+
+#[test]
+fn test_random_move_is_empty() {
+    let env = Env::default();
+    let client = setup(&env);
+
+    // X on cell 0 and O on cell 4
+    let grid = 0b000000001000000001;
+    let cell = client.best_move(&grid, &0b01, &0);
+
+    assert!(cell != 0 && cell != 4 && cell <= 8);
+}
+
+#[test]
+#[should_panic(expected = "Grid is full")]
+fn test_full_grid() {
+    let env = Env::default();
+    let client = setup(&env);
+
+    // X O X / X O O / O X X, from cell 0
+    client.best_move(&0b010110101001011001, &0b01, &100);
+}
+
+#[test]
+fn test_book() {
+    // Every position with up to 4 marks that a game can reach with `mark` on turn
+    for position in 0..3u32.pow(9) {
+        let (mut grid, mut marks, mut rest) = (0, [0; 3], position);
+        for cell in 0..9 {
+            grid |= (rest % 3) << (cell * 2);
+            marks[(rest % 3) as usize] += 1;
+            rest /= 3;
+        }
+        for mark in [0b01, 0b10] {
+            let (own, other) = (marks[mark as usize], marks[(mark ^ 0b11) as usize]);
+            if own + other > 4 || !(own == other || own + 1 == other) {
+                continue;
+            }
+            assert_eq!(
+                solver::book_score(grid, mark),
+                Some(solver::negamax(grid, mark, -1, 1))
+            );
+        }
+    }
+}
+
+#[test]
+fn test_budget() {
+    let env = Env::default();
+    env.budget().reset_unlimited();
+    let client = wasm_bot::Client::new(&env, &env.register_contract_wasm(None, wasm_bot::WASM));
+
+    // Every reply to an opening move, the most expensive one to search
+    for cell in 0..9 {
+        env.budget().reset_default();
+        let reply = client.best_move(&(0b01 << (cell * 2)), &0b10, &100);
+        assert!(reply != cell);
+        std::println!("COST {} {}", cell, env.budget().cpu_instruction_cost());
+    }
+}
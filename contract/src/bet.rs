@@ -1,9 +1,10 @@
-use crate::game::{get_player_a, get_player_b, get_winner, has_ended, has_winner};
+use crate::config::get_config;
+use crate::game::{get_player_a, get_player_b, get_winner, has_ended, has_winner, settle_expiry};
 use crate::storage::DataKey;
 use core::cmp::{max, min};
 use soroban_sdk::{contracttype, token, vec, Address, Env, Vec};
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
 pub struct Bet {
     pub token: Address,
@@ -18,6 +19,13 @@ fn has_bet(env: &Env, player: Address) -> bool {
     }
 }
 
+pub fn get_bets(env: &Env) -> (Option<Bet>, Option<Bet>) {
+    (
+        env.storage().instance().get(&DataKey::BetPlayerA),
+        env.storage().instance().get(&DataKey::BetPlayerB),
+    )
+}
+
 fn add_bet(env: &Env, player: Address, amount: i128) -> Bet {
     let mut bet = get_bet(env, player.clone());
     bet.amount += amount;
@@ -70,6 +78,7 @@ pub fn make(env: &Env, player: Address, token: Address, amount: i128) -> Bet {
 pub fn collect(env: &Env, player: Address) -> Vec<Bet> {
     player.require_auth();
     assert!(has_bet(env, player.clone()), "You don't have a bet");
+    settle_expiry(env);
     assert!(has_ended(env), "Game is still being played");
 
     let mut bet = get_bet(env, player.clone());
@@ -112,6 +121,62 @@ pub fn collect(env: &Env, player: Address) -> Vec<Bet> {
     res
 }
 
+// Returns every bet that hasn't been paid to its player
+pub fn refund(env: &Env) {
+    for player in [get_player_a(env), get_player_b(env)] {
+        if !has_bet(env, player.clone()) {
+            continue;
+        }
+        let mut bet = get_bet(env, player.clone());
+        if bet.paid {
+            continue;
+        }
+        pay(env, &player, bet.token.clone(), bet.amount);
+        bet.paid = true;
+        set_bet(env, player, bet);
+    }
+}
+
+pub fn get_pot(env: &Env) -> i128 {
+    env.storage().instance().get(&DataKey::Pot).unwrap_or(0)
+}
+
+pub fn add_to_pot(env: &Env, amount: i128) {
+    env.storage()
+        .instance()
+        .set(&DataKey::Pot, &(get_pot(env) + amount));
+}
+
+pub fn collect_pot(env: &Env, player: Address) -> i128 {
+    player.require_auth();
+    if player != get_player_a(env) && player != get_player_b(env) {
+        panic!("You are not allowed to collect the pot");
+    }
+    settle_expiry(env);
+    assert!(has_ended(env), "Game is still being played");
+
+    let paid_key = DataKey::PotPaid(player.clone());
+    assert!(
+        !env.storage().instance().has(&paid_key),
+        "You have already been paid"
+    );
+
+    let pot = get_pot(env);
+    // On a draw player_a gets the odd unit of the pot, whoever played first
+    let amount = match has_winner(env) {
+        true if get_winner(env) == player => pot,
+        true => 0,
+        false if player == get_player_a(env) => pot - pot / 2,
+        false => pot / 2,
+    };
+    if let Some(fee_token) = get_config(env).fee_token.to_option() {
+        pay(env, &player, fee_token, amount);
+    }
+
+    env.storage().instance().set(&paid_key, &true);
+    amount
+}
+
 fn pay(env: &Env, to: &Address, token: Address, amount: i128) {
     if amount <= 0 {
         return;
@@ -0,0 +1,20 @@
+use crate::game::{get_grid, get_player_b, get_player_turn};
+use soroban_sdk::{contractclient, Env};
+
+#[contractclient(name = "BotClient")]
+pub trait BotInterface {
+    fn best_move(env: Env, grid: u32, mark: u32, level: u32) -> u32;
+}
+
+// Asks the bot registered as player_b for its move, as a cell (pos_y * 3 + pos_x)
+pub fn reply(env: &Env, level: u32) -> (u32, u32) {
+    let bot = get_player_b(env);
+    assert!(get_player_turn(env) == bot, "It's not the bot's turn");
+
+    let grid = get_grid(env);
+    let cell = BotClient::new(env, &bot).best_move(&grid, &0b10, &level);
+    assert!(cell <= 8, "Bot move out of range");
+    assert!((grid >> (cell * 2)) & 0b11 == 0, "Bot played a used cell");
+
+    (cell % 3, cell / 3)
+}
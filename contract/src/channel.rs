@@ -0,0 +1,131 @@
+use crate::config::{get_config, Rules, Variant};
+use crate::game::{
+    get_grid, get_player_a, get_player_b, get_time, has_ended, has_players, is_full, line_winner,
+    set_grid, set_player_turn, set_status, set_time, set_winner_mark, GameStatus,
+};
+use crate::signed::verify;
+use crate::storage::DataKey;
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{contracttype, Address, BytesN, Env};
+
+// Board state signed by both players while playing off-chain
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct ChannelState {
+    pub game: Address,
+    // Increased by the players on every signed state, the highest one wins a dispute
+    pub seq: u32,
+    pub grid: u32,
+    pub moves: u32,
+    pub turn: Address,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Dispute {
+    pub state: ChannelState,
+    pub deadline: u32,
+}
+
+// Starts a dispute with a co-signed state, or challenges the current one with
+// a later state. The state is applied by `settle` once the challenge period ends.
+pub fn submit(env: &Env, state: ChannelState, sig_a: BytesN<64>, sig_b: BytesN<64>) -> u32 {
+    assert!(has_players(env), "Game is not initialized");
+    let config = get_config(env);
+    // `settle` decides the result by lines, as the standard rules do
+    assert!(
+        config.variant == Variant::Classic && config.rules == Rules::Standard,
+        "Channels are only available in classic games with standard rules"
+    );
+    assert!(!has_ended(env), "Game has ended");
+    assert!(
+        state.game == env.current_contract_address(),
+        "State is for another game"
+    );
+    assert_valid(env, &state);
+    // Signatures of a settled state are public, it must not be replayed
+    if let Some(seq) = get_settled_seq(env) {
+        assert!(state.seq > seq, "State was already settled");
+    }
+
+    if let Some(dispute) = get_dispute(env) {
+        assert!(
+            env.ledger().sequence() <= dispute.deadline,
+            "Challenge period is over"
+        );
+        assert!(state.seq > dispute.state.seq, "State is not newer");
+    }
+
+    let payload = state.clone().to_xdr(env);
+    verify(env, &get_player_a(env), &payload, &sig_a);
+    verify(env, &get_player_b(env), &payload, &sig_b);
+
+    let deadline = env.ledger().sequence() + config.challenge_period;
+    env.storage()
+        .instance()
+        .set(&DataKey::Dispute, &Dispute { state, deadline });
+    deadline
+}
+
+// Applies the disputed state. If it doesn't end the game, play continues on-chain
+pub fn settle(env: &Env) {
+    let dispute = get_dispute(env).expect("No state submitted");
+    assert!(!has_ended(env), "Game has ended");
+    assert!(
+        env.ledger().sequence() > dispute.deadline,
+        "Challenge period is still open"
+    );
+    env.storage().instance().remove(&DataKey::Dispute);
+    env.storage()
+        .instance()
+        .set(&DataKey::SettledSeq, &dispute.state.seq);
+
+    let state = dispute.state;
+    set_grid(env, state.grid);
+    set_time(env, state.moves);
+    set_player_turn(env, &state.turn);
+
+    if set_winner_mark(env, line_winner(state.grid)) {
+        set_status(env, GameStatus::Won);
+    } else if is_full(state.grid) {
+        set_status(env, GameStatus::Draw);
+    } else if state.moves > 0 {
+        set_status(env, GameStatus::InProgress);
+    }
+}
+
+pub fn get_dispute(env: &Env) -> Option<Dispute> {
+    env.storage().instance().get(&DataKey::Dispute)
+}
+
+fn get_settled_seq(env: &Env) -> Option<u32> {
+    env.storage().instance().get(&DataKey::SettledSeq)
+}
+
+pub fn has_dispute(env: &Env) -> bool {
+    env.storage().instance().has(&DataKey::Dispute)
+}
+
+fn assert_valid(env: &Env, state: &ChannelState) {
+    assert!(state.grid >> 18 == 0, "Invalid grid");
+    let mut marked = 0;
+    for cell in 0..9 {
+        match (state.grid >> (cell * 2)) & 0b11 {
+            0b00 => {}
+            0b11 => panic!("Invalid grid"),
+            _ => marked += 1,
+        }
+    }
+    assert!(marked == state.moves, "Moves don't match the grid");
+    assert!(state.moves >= get_time(env), "State is older than the game");
+
+    let grid = get_grid(env);
+    assert!(
+        state.grid & grid == grid,
+        "State doesn't include the played moves"
+    );
+    assert!(
+        state.turn == get_player_a(env) || state.turn == get_player_b(env),
+        "Turn is not a player"
+    );
+}
@@ -1,29 +1,161 @@
-use crate::storage::DataKey;
-use soroban_sdk::{contracttype, vec, Address, Env, Symbol, Vec};
+use crate::bet::add_to_pot;
+use crate::config::{get_config, Config};
+use crate::game::{get_player_a, get_player_b};
+use crate::session;
+use crate::storage::{DataKey, CHAT_BUMP_AMOUNT};
+use core::cmp::min;
+use soroban_sdk::{contracttype, token, vec, Address, Env, String, Vec};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[contracttype]
 pub struct Message {
     pub author: Address,
-    pub body: Symbol,
+    pub body: String,
+    pub timestamp: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct RateWindow {
+    pub start: u32,
+    pub count: u32,
 }
 
 pub fn get_chats(env: &Env) -> Vec<Message> {
     env.storage()
-        .instance()
+        .temporary()
         .get(&DataKey::Chats)
         .unwrap_or(vec![env])
 }
 
-pub fn add_msg(env: &Env, player: Address, message: Symbol) -> Message {
-    player.require_auth();
+pub fn get_page(env: &Env, offset: u32, limit: u32) -> Vec<Message> {
+    let chats = get_chats(env);
+    let start = min(offset, chats.len());
+    let end = min(start.saturating_add(limit), chats.len());
+    chats.slice(start..end)
+}
+
+pub fn add_msg(env: &Env, player: Address, message: String) -> Message {
+    let player = session::authorize(env, player, "send_msg");
+    assert!(can_chat(env, &player), "You are not allowed to chat");
+    assert!(!is_muted(env, &player), "You have been muted");
+
+    let config = get_config(env);
+    let len = message.len();
+    assert!(len > 0, "Message is empty");
+    assert!(len <= config.chat_max_len, "Message is too long");
+
+    check_rate(env, &config, &player);
+    charge_fee(env, &config, &player);
 
     let mut chats = get_chats(env);
+    while chats.len() >= config.chat_cap {
+        chats.pop_front();
+    }
     let msg = Message {
         author: player,
         body: message,
+        timestamp: env.ledger().timestamp(),
     };
     chats.push_back(msg.clone());
-    env.storage().instance().set(&DataKey::Chats, &chats);
+    env.storage().temporary().set(&DataKey::Chats, &chats);
+    bump(env);
     msg
 }
+
+pub fn mute(env: &Env, player: Address, target: Address) {
+    player.require_auth();
+    assert!(is_player(env, &player), "Only players can mute");
+    assert!(player != target, "You can't mute yourself");
+
+    let mut muted = get_muted(env, &player);
+    if !muted.contains(&target) {
+        muted.push_back(target);
+        set_muted(env, &player, &muted);
+    }
+}
+
+pub fn unmute(env: &Env, player: Address, target: Address) {
+    player.require_auth();
+    assert!(is_player(env, &player), "Only players can unmute");
+
+    let mut muted = get_muted(env, &player);
+    if let Some(index) = muted.first_index_of(&target) {
+        muted.remove(index);
+        set_muted(env, &player, &muted);
+    }
+}
+
+pub fn bump(env: &Env) {
+    if env.storage().temporary().has(&DataKey::Chats) {
+        env.storage()
+            .temporary()
+            .bump(&DataKey::Chats, CHAT_BUMP_AMOUNT);
+    }
+}
+
+fn check_rate(env: &Env, config: &Config, author: &Address) {
+    if config.chat_rate == 0 {
+        return;
+    }
+    let key = DataKey::ChatRate(author.clone());
+    let sequence = env.ledger().sequence();
+    let mut window = env
+        .storage()
+        .temporary()
+        .get(&key)
+        .unwrap_or(RateWindow {
+            start: sequence,
+            count: 0,
+        });
+    if sequence >= window.start + config.chat_window {
+        window = RateWindow {
+            start: sequence,
+            count: 0,
+        };
+    }
+    assert!(window.count < config.chat_rate, "Too many messages");
+
+    window.count += 1;
+    env.storage().temporary().set(&key, &window);
+    env.storage().temporary().bump(&key, config.chat_window);
+}
+
+fn charge_fee(env: &Env, config: &Config, author: &Address) {
+    if config.chat_fee == 0 {
+        return;
+    }
+    let fee_token = config.fee_token.to_option().unwrap();
+    token::Client::new(env, &fee_token).transfer(
+        author,
+        &env.current_contract_address(),
+        &config.chat_fee,
+    );
+    add_to_pot(env, config.chat_fee);
+}
+
+fn is_player(env: &Env, player: &Address) -> bool {
+    *player == get_player_a(env) || *player == get_player_b(env)
+}
+
+fn can_chat(env: &Env, player: &Address) -> bool {
+    is_player(env, player) || get_config(env).spectators
+}
+
+fn is_muted(env: &Env, author: &Address) -> bool {
+    get_muted(env, &get_player_a(env)).contains(author)
+        || get_muted(env, &get_player_b(env)).contains(author)
+}
+
+pub fn get_muted(env: &Env, player: &Address) -> Vec<Address> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Muted(player.clone()))
+        .unwrap_or(vec![env])
+}
+
+fn set_muted(env: &Env, player: &Address, muted: &Vec<Address>) {
+    env.storage()
+        .instance()
+        .set(&DataKey::Muted(player.clone()), muted);
+}
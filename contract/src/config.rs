@@ -0,0 +1,184 @@
+use crate::maybe::{MaybeAddress, MaybeBytes32};
+use crate::storage::DataKey;
+use soroban_sdk::{contracttype, Env};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum MoveMode {
+    Open,
+    CommitReveal,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Variant {
+    Classic,
+    Ultimate,
+    // Three pieces each, then pieces are moved instead of placed
+    Sliding,
+}
+
+// How a line decides the game on the classic grid
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Rules {
+    Standard,
+    // Making three in a row loses
+    Misere,
+    // Each move chooses X or O, any line wins
+    Wild,
+    // Both marks are free, player_a wins with any line and player_b with a full board
+    OrderChaos,
+}
+
+// Who plays the first move
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum StartPolicy {
+    // player_a always starts
+    Fixed,
+    // Picked from a hash of the ledger and the players at init
+    Random,
+    // Both players commit a secret and reveal it with flip_commit and flip_reveal
+    CoinFlip,
+    // player_a starts the even games of a series and player_b the odd ones
+    Alternate,
+}
+
+// What a game that expires before ending results in
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum ExpiryPolicy {
+    // Ended without a winner, nobody scores
+    NoResult,
+    // The player on move loses
+    OnMove,
+    Draw,
+    // The board decides, see game::adjudicate
+    Board,
+    // The outcome with perfect play from the final board
+    Solver,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Config {
+    // In bytes, the contract doesn't decode the UTF-8 of messages
+    pub chat_max_len: u32,
+    pub spectators: bool,
+    // Messages allowed per sender every `chat_window` ledgers, 0 disables the limit
+    pub chat_rate: u32,
+    pub chat_window: u32,
+    // Stored messages, the oldest ones are dropped first
+    pub chat_cap: u32,
+    pub chat_fee: i128,
+    pub fee_token: MaybeAddress,
+    pub move_mode: MoveMode,
+    // Ledgers a player has to reveal a committed move
+    pub reveal_window: u32,
+    pub variant: Variant,
+    pub rules: Rules,
+    // Moves, placements included, after which a sliding game is a draw
+    pub move_limit: u32,
+    // player_b is a bot contract that replies to every move
+    pub bot: bool,
+    // Chance, from 0 to 100, of the bot playing the perfect move
+    pub bot_level: u32,
+    // Ed25519 keys that can sign moves for player_a and player_b
+    pub key_a: MaybeBytes32,
+    pub key_b: MaybeBytes32,
+    // Ledgers a submitted channel state can be challenged
+    pub challenge_period: u32,
+    pub start: StartPolicy,
+    // Games these players already played in the series, used by the Alternate policy
+    pub series_game: u32,
+    pub on_expiry: ExpiryPolicy,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            chat_max_len: 140,
+            spectators: false,
+            chat_rate: 5,
+            chat_window: 10,
+            chat_cap: 100,
+            chat_fee: 0,
+            fee_token: MaybeAddress::None,
+            move_mode: MoveMode::Open,
+            reveal_window: 100,
+            variant: Variant::Classic,
+            rules: Rules::Standard,
+            move_limit: 30,
+            bot: false,
+            bot_level: 100,
+            key_a: MaybeBytes32::None,
+            key_b: MaybeBytes32::None,
+            challenge_period: 100,
+            start: StartPolicy::Fixed,
+            series_game: 0,
+            on_expiry: ExpiryPolicy::NoResult,
+        }
+    }
+}
+
+pub fn get_config(env: &Env) -> Config {
+    env.storage()
+        .instance()
+        .get(&DataKey::Config)
+        .unwrap_or_default()
+}
+
+pub fn set_config(env: &Env, config: &Config) {
+    assert!(config.chat_max_len > 0, "Chat max length must be positive");
+    assert!(config.chat_cap > 0, "Chat cap must be positive");
+    assert!(
+        config.chat_rate == 0 || config.chat_window > 0,
+        "Chat window must be positive"
+    );
+    assert!(config.chat_fee >= 0, "Chat fee can't be negative");
+    assert!(
+        config.chat_fee == 0 || config.fee_token.is_some(),
+        "Chat fee requires a token"
+    );
+    assert!(config.reveal_window > 0, "Reveal window must be positive");
+    assert!(
+        config.variant == Variant::Classic || config.move_mode == MoveMode::Open,
+        "Commit-reveal is only available in classic games"
+    );
+    assert!(
+        config.variant == Variant::Classic || config.rules == Rules::Standard,
+        "Rules are only available in classic games"
+    );
+    assert!(
+        config.variant != Variant::Sliding || config.move_limit > 6,
+        "Move limit must allow moving pieces"
+    );
+    assert!(config.bot_level <= 100, "Bot level out of range");
+    assert!(
+        !config.bot
+            || (config.variant == Variant::Classic
+                && config.rules == Rules::Standard
+                && config.move_mode == MoveMode::Open),
+        "Bots only play classic games"
+    );
+    assert!(
+        config.move_mode == MoveMode::Open || !free_marks(config.rules),
+        "Commit-reveal needs fixed marks"
+    );
+    assert!(
+        !config.bot || config.start == StartPolicy::Fixed,
+        "Bots need player_a to start"
+    );
+    assert!(
+        !matches!(config.on_expiry, ExpiryPolicy::Board | ExpiryPolicy::Solver)
+            || (config.variant == Variant::Classic && config.rules == Rules::Standard),
+        "Board adjudication needs a classic game with standard rules"
+    );
+    env.storage().instance().set(&DataKey::Config, config);
+}
+
+// Rules where players choose the mark of every move
+pub fn free_marks(rules: Rules) -> bool {
+    matches!(rules, Rules::Wild | Rules::OrderChaos)
+}
@@ -0,0 +1,131 @@
+use crate::config::{get_config, StartPolicy};
+use crate::game::{
+    forfeit_player, get_player_a, get_player_b, has_ended, has_players, set_player_turn,
+};
+use crate::maybe::MaybeBytes32;
+use crate::storage::DataKey;
+use soroban_sdk::{contracttype, Address, Bytes, BytesN, Env};
+
+// Each player commits sha256(secret), once both secrets are revealed the
+// parity of their last bytes picks who starts, so neither can choose alone
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Flip {
+    pub hash_a: MaybeBytes32,
+    pub hash_b: MaybeBytes32,
+    pub secret_a: MaybeBytes32,
+    pub secret_b: MaybeBytes32,
+    // Last ledger to reveal in, set once both players committed
+    pub deadline: u32,
+}
+
+pub fn commit(env: &Env, player: Address, hash: BytesN<32>) {
+    player.require_auth();
+    assert_flip(env);
+
+    let mut flip = get_flip(env);
+    if player == get_player_a(env) {
+        assert!(flip.hash_a.is_none(), "Already committed");
+        flip.hash_a = MaybeBytes32::Some(hash);
+    } else if player == get_player_b(env) {
+        assert!(flip.hash_b.is_none(), "Already committed");
+        flip.hash_b = MaybeBytes32::Some(hash);
+    } else {
+        panic!("You are not a player");
+    }
+    if flip.hash_a.is_some() && flip.hash_b.is_some() {
+        flip.deadline = env.ledger().sequence() + get_config(env).reveal_window;
+    }
+    set_flip(env, &flip);
+}
+
+// Returns the first player once both secrets are revealed
+pub fn reveal(env: &Env, player: Address, secret: BytesN<32>) -> Option<Address> {
+    player.require_auth();
+    assert_flip(env);
+
+    let mut flip = get_flip(env);
+    assert!(
+        flip.hash_a.is_some() && flip.hash_b.is_some(),
+        "Both players must commit first"
+    );
+    assert!(
+        env.ledger().sequence() <= flip.deadline,
+        "Reveal window is over"
+    );
+    let hash = MaybeBytes32::Some(env.crypto().sha256(&Bytes::from(secret.clone())));
+    if player == get_player_a(env) {
+        assert!(flip.secret_a.is_none(), "Already revealed");
+        assert!(hash == flip.hash_a, "Secret doesn't match the commit");
+        flip.secret_a = MaybeBytes32::Some(secret);
+    } else if player == get_player_b(env) {
+        assert!(flip.secret_b.is_none(), "Already revealed");
+        assert!(hash == flip.hash_b, "Secret doesn't match the commit");
+        flip.secret_b = MaybeBytes32::Some(secret);
+    } else {
+        panic!("You are not a player");
+    }
+    set_flip(env, &flip);
+
+    let (secret_a, secret_b) = match (flip.secret_a.to_option(), flip.secret_b.to_option()) {
+        (Some(secret_a), Some(secret_b)) => (secret_a, secret_b),
+        _ => return None,
+    };
+    let first = match (secret_a.get(31).unwrap() ^ secret_b.get(31).unwrap()) & 1 {
+        0 => get_player_a(env),
+        _ => get_player_b(env),
+    };
+    set_player_turn(env, &first);
+    Some(first)
+}
+
+// Once the reveal window is over, a player who didn't reveal forfeits the game.
+// If neither did, player_a starts as with the fixed policy.
+pub fn claim(env: &Env) {
+    assert_flip(env);
+    let flip = get_flip(env);
+    assert!(
+        flip.hash_a.is_some() && flip.hash_b.is_some(),
+        "Both players must commit first"
+    );
+    assert!(
+        env.ledger().sequence() > flip.deadline,
+        "Reveal window is still open"
+    );
+    assert!(
+        !env.storage().instance().has(&DataKey::PlayerTurn),
+        "The first player is decided"
+    );
+
+    match (flip.secret_a.is_some(), flip.secret_b.is_some()) {
+        (true, false) => forfeit_player(env, &get_player_b(env)),
+        (false, true) => forfeit_player(env, &get_player_a(env)),
+        _ => set_player_turn(env, &get_player_a(env)),
+    }
+}
+
+pub fn get_flip(env: &Env) -> Flip {
+    env.storage()
+        .instance()
+        .get(&DataKey::Flip)
+        .unwrap_or(Flip {
+            hash_a: MaybeBytes32::None,
+            hash_b: MaybeBytes32::None,
+            secret_a: MaybeBytes32::None,
+            secret_b: MaybeBytes32::None,
+            deadline: 0,
+        })
+}
+
+fn set_flip(env: &Env, flip: &Flip) {
+    env.storage().instance().set(&DataKey::Flip, flip)
+}
+
+fn assert_flip(env: &Env) {
+    assert!(has_players(env), "Game is not initialized");
+    assert!(
+        get_config(env).start == StartPolicy::CoinFlip,
+        "Game doesn't start with a coin flip"
+    );
+    assert!(!has_ended(env), "Game has ended");
+}
@@ -1,36 +1,411 @@
+use crate::analyze;
+use crate::bet;
+use crate::bot;
+use crate::channel;
+use crate::config::{
+    free_marks, get_config, set_config, Config, ExpiryPolicy, MoveMode, Rules, StartPolicy, Variant,
+};
+use crate::session;
+use crate::sliding;
 use crate::storage::DataKey;
-use soroban_sdk::{symbol_short, vec, Address, Env, Symbol, Vec};
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{contracttype, symbol_short, vec, Address, Bytes, BytesN, Env, Symbol, Vec};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum GameStatus {
+    NotStarted,
+    InProgress,
+    Won,
+    Draw,
+    Expired,
+    Resigned,
+    Forfeited,
+    Cancelled,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Mark {
+    X,
+    O,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Commit {
+    pub player: Address,
+    pub hash: BytesN<32>,
+    pub deadline: u32,
+}
 
-pub fn init(env: Env, player_a: Address, player_b: Address, expiration: u64) {
+// Pieces each player places in sliding games
+pub const PIECES: u32 = 3;
+
+pub fn init(env: Env, player_a: Address, player_b: Address, expiration: u64, config: Config) {
     assert!(!has_players(&env), "Already initialized");
+    // A signing key plays for its player, so only that player can set it
+    if config.key_a.is_some() {
+        player_a.require_auth();
+    }
+    if config.key_b.is_some() {
+        player_b.require_auth();
+    }
     set_players(&env, &player_a, &player_b);
     set_expiration(&env, expiration);
+    set_config(&env, &config);
+    set_status(&env, GameStatus::NotStarted);
+    set_first_player(&env, &config, &player_a, &player_b);
+}
+
+// With a coin flip the turn stays unset until both secrets are revealed
+fn set_first_player(env: &Env, config: &Config, player_a: &Address, player_b: &Address) {
+    let a_starts = match config.start {
+        StartPolicy::Fixed => true,
+        StartPolicy::Random => random_bit(env, player_a, player_b) == 0,
+        StartPolicy::Alternate => config.series_game % 2 == 0,
+        StartPolicy::CoinFlip => return,
+    };
+    match a_starts {
+        true => set_player_turn(env, player_a),
+        false => set_player_turn(env, player_b),
+    }
+}
+
+// The host has no random generator, so the draw hashes the ledger with both
+// players. It is only as fair as the ledger is unpredictable to whoever inits.
+fn random_bit(env: &Env, player_a: &Address, player_b: &Address) -> u8 {
+    let mut seed = Bytes::new(env);
+    seed.extend_from_array(&env.ledger().sequence().to_be_bytes());
+    seed.extend_from_array(&env.ledger().timestamp().to_be_bytes());
+    seed.append(&player_a.clone().to_xdr(env));
+    seed.append(&player_b.clone().to_xdr(env));
+    env.crypto().sha256(&seed).get_unchecked(0) & 1
 }
 
 pub fn play(env: Env, player: Address, pos_x: u32, pos_y: u32) -> Vec<Symbol> {
-    assert!(has_players(&env), "Game is not initialized");
-    assert!(allowed_player(&env, player), "It's not your turn");
-    assert!(!has_ended(&env), "Game has ended");
+    let player = session::authorize(&env, player, "play");
+    let config = assert_classic_move(&env, &player, pos_x, pos_y);
+    play_checked(&env, &config, pos_x, pos_y);
+
+    grid(env)
+}
+
+// Plays a move that already passed `assert_classic_move`, and the bot's reply
+pub fn play_checked(env: &Env, config: &Config, pos_x: u32, pos_y: u32) {
+    assert!(!free_marks(config.rules), "Choose a mark with play_mark");
+
+    apply_move(env, pos_x, pos_y, player_mark(env));
+    if config.bot && !has_ended(env) {
+        let (bot_x, bot_y) = bot::reply(env, config.bot_level);
+        apply_move(env, bot_x, bot_y, player_mark(env));
+    }
+}
+
+pub fn play_mark(env: Env, player: Address, pos_x: u32, pos_y: u32, mark: Mark) -> Vec<Symbol> {
+    let player = session::authorize(&env, player, "play_mark");
+    let config = assert_classic_move(&env, &player, pos_x, pos_y);
+    assert!(free_marks(config.rules), "Marks can't be chosen with these rules");
+
+    let mark = match mark {
+        Mark::X => 0b01,
+        Mark::O => 0b10,
+    };
+    apply_move(&env, pos_x, pos_y, mark);
+
+    grid(env)
+}
+
+pub fn assert_classic_move(env: &Env, player: &Address, pos_x: u32, pos_y: u32) -> Config {
+    assert!(has_players(env), "Game is not initialized");
+    let config = get_config(env);
+    assert!(
+        config.variant != Variant::Ultimate,
+        "Use play_ult in ultimate games"
+    );
+    assert!(
+        config.variant != Variant::Sliding || get_time(env) < PIECES * 2,
+        "Move a piece with move_piece"
+    );
+    assert!(config.move_mode == MoveMode::Open, "Moves must be committed");
+    assert_can_move(env, player);
     assert!(pos_x <= 2, "X position out of range");
     assert!(pos_y <= 2, "Y position out of range");
-    assert!(is_empty_cell(&env, pos_x, pos_y), "Cell is already used");
+    assert!(is_empty_cell(env, pos_x, pos_y), "Cell is already used");
+    config
+}
+
+pub fn commit(env: &Env, player: Address, hash: BytesN<32>) -> u32 {
+    assert!(has_players(env), "Game is not initialized");
+    let config = get_config(env);
+    assert!(
+        config.move_mode == MoveMode::CommitReveal,
+        "Game is not in commit-reveal mode"
+    );
+    let player = session::authorize(env, player, "commit");
+    assert_can_move(env, &player);
+    assert!(get_commit(env).is_none(), "Move already committed");
+
+    let deadline = env.ledger().sequence() + config.reveal_window;
+    env.storage().instance().set(
+        &DataKey::Commit,
+        &Commit {
+            player,
+            hash,
+            deadline,
+        },
+    );
+    deadline
+}
+
+// A reveal that doesn't match the commit, comes too late or isn't a legal move
+// forfeits the game instead of failing, so the result is stored
+pub fn reveal(env: Env, player: Address, pos_x: u32, pos_y: u32, salt: BytesN<32>) -> Vec<Symbol> {
+    let player = session::authorize(&env, player, "reveal");
+    let commit = get_commit(&env).expect("No move committed");
+    assert!(commit.player == player, "It's not your turn");
+    assert!(!channel::has_dispute(&env), "A channel state is being settled");
+    assert!(!has_ended(&env), "Game has ended");
+    assert!(!is_expired(&env), "Game has expired");
 
-    mark_cell(&env, pos_x, pos_y);
-    check_winner(&env);
+    let valid = env.ledger().sequence() <= commit.deadline
+        && move_hash(&env, pos_x, pos_y, &salt) == commit.hash
+        && pos_x <= 2
+        && pos_y <= 2
+        && is_empty_cell(&env, pos_x, pos_y);
 
-    change_turn(&env);
-    increase_time(&env);
+    env.storage().instance().remove(&DataKey::Commit);
+    if valid {
+        apply_move(&env, pos_x, pos_y, player_mark(&env));
+    } else {
+        forfeit_player(&env, &player);
+    }
 
     grid(env)
 }
 
+pub fn claim_forfeit(env: &Env) {
+    let commit = get_commit(env).expect("No move committed");
+    assert!(!has_ended(env), "Game has ended");
+    assert!(
+        env.ledger().sequence() > commit.deadline,
+        "Reveal window is still open"
+    );
+
+    env.storage().instance().remove(&DataKey::Commit);
+    forfeit_player(env, &commit.player);
+}
+
+// sha256(pos_x || pos_y || salt), positions as big endian u32
+pub fn move_hash(env: &Env, pos_x: u32, pos_y: u32, salt: &BytesN<32>) -> BytesN<32> {
+    let mut data = Bytes::new(env);
+    data.extend_from_array(&pos_x.to_be_bytes());
+    data.extend_from_array(&pos_y.to_be_bytes());
+    data.append(&Bytes::from(salt.clone()));
+    env.crypto().sha256(&data)
+}
+
+pub fn get_commit(env: &Env) -> Option<Commit> {
+    env.storage().instance().get(&DataKey::Commit)
+}
+
+pub fn forfeit_player(env: &Env, player: &Address) {
+    set_winner(env, get_opponent(env, player));
+    set_status(env, GameStatus::Forfeited);
+}
+
+fn apply_move(env: &Env, pos_x: u32, pos_y: u32, mark: u32) {
+    let (offset, _) = get_cell_pos(pos_x, pos_y);
+    let grid = get_grid(env) | mark << offset;
+    set_grid(env, grid);
+
+    let config = get_config(env);
+    let full = get_time(env) + 1 >= 9;
+    let mut winner = rules_winner(env, config.rules, grid, full);
+    // The first player to slide loses if the last piece placed leaves them stuck
+    if winner.is_none()
+        && config.variant == Variant::Sliding
+        && get_time(env) + 1 == PIECES * 2
+        && sliding::is_blocked(grid, player_mark(env) ^ 0b11)
+    {
+        winner = Some(get_player_turn(env));
+    }
+    if let Some(winner) = winner.clone() {
+        set_winner(env, winner);
+    }
+    end_turn(env, winner.is_some(), full);
+}
+
+// Decides the winner after the player on turn moved, if the move ended the game
+fn rules_winner(env: &Env, rules: Rules, grid: u32, full: bool) -> Option<Address> {
+    let line = line_winner(grid);
+    let mover = get_player_turn(env);
+    match rules {
+        Rules::Standard => match line {
+            0b01 => Some(get_player_a(env)),
+            0b10 => Some(get_player_b(env)),
+            _ => None,
+        },
+        Rules::Misere if line != 0 => Some(get_opponent(env, &mover)),
+        Rules::Wild if line != 0 => Some(mover),
+        Rules::OrderChaos if line != 0 => Some(get_player_a(env)),
+        Rules::OrderChaos if full => Some(get_player_b(env)),
+        _ => None,
+    }
+}
+
+pub fn assert_can_move(env: &Env, player: &Address) {
+    assert!(get_player_turn(env) == *player, "It's not your turn");
+    assert!(!channel::has_dispute(env), "A channel state is being settled");
+    assert!(!has_ended(env), "Game has ended");
+    assert!(!is_expired(env), "Game has expired");
+}
+
+pub fn end_turn(env: &Env, won: bool, full: bool) {
+    change_turn(env);
+    increase_time(env);
+    // Removing a missing instance key fails, and most moves have no offer to clear
+    if env.storage().instance().has(&DataKey::DrawOffer) {
+        env.storage().instance().remove(&DataKey::DrawOffer);
+    }
+
+    if won {
+        set_status(env, GameStatus::Won);
+    } else if full {
+        set_status(env, GameStatus::Draw);
+    } else {
+        set_status(env, GameStatus::InProgress);
+    }
+}
+
+pub fn expire(env: &Env) {
+    assert!(has_players(env), "Game is not initialized");
+    assert!(!has_ended(env), "Game has ended");
+    assert!(is_expired(env), "Game has not expired");
+    assert!(!channel::has_dispute(env), "A channel state is being settled");
+
+    // A game nobody played has nothing to judge, whatever the policy
+    let policy = match status(env) {
+        GameStatus::NotStarted => ExpiryPolicy::NoResult,
+        _ => get_config(env).on_expiry,
+    };
+    let turn: Option<Address> = env.storage().instance().get(&DataKey::PlayerTurn);
+    let winner = match policy {
+        ExpiryPolicy::NoResult => {
+            set_status(env, GameStatus::Expired);
+            return;
+        }
+        ExpiryPolicy::OnMove => turn.map(|player| get_opponent(env, &player)),
+        ExpiryPolicy::Draw => None,
+        ExpiryPolicy::Board => turn.and_then(|player| adjudicate(env, player)),
+        ExpiryPolicy::Solver => turn.and_then(|_| analyze::solved_winner(env)),
+    };
+    match winner {
+        Some(winner) => {
+            set_winner(env, winner);
+            set_status(env, GameStatus::Expired);
+        }
+        None => set_status(env, GameStatus::Draw),
+    }
+}
+
+// Expires the game if it's due, so results can be collected without calling expire first
+pub fn settle_expiry(env: &Env) {
+    if has_players(env) && !has_ended(env) && is_expired(env) && !channel::has_dispute(env) {
+        expire(env);
+    }
+}
+
+// The player on move wins with a line to complete, the opponent wins with two
+// since only one can be blocked, anything else is a draw
+fn adjudicate(env: &Env, mover: Address) -> Option<Address> {
+    let grid = get_grid(env);
+    let mark = player_mark(env);
+    if threats(grid, mark) > 0 {
+        Some(mover)
+    } else if threats(grid, mark ^ 0b11) > 1 {
+        Some(get_opponent(env, &mover))
+    } else {
+        None
+    }
+}
+
+// Lines where `mark` has two cells and the third one is empty
+fn threats(grid: u32, mark: u32) -> u32 {
+    let mut count = 0;
+    for line in LINES {
+        let (mut own, mut empty) = (0, 0);
+        for cell in 0..9 {
+            if (line >> (cell * 2)) & 1 == 0 {
+                continue;
+            }
+            match (grid >> (cell * 2)) & 0b11 {
+                0 => empty += 1,
+                val if val == mark => own += 1,
+                _ => {}
+            }
+        }
+        if own == 2 && empty == 1 {
+            count += 1;
+        }
+    }
+    count
+}
+
+// A player can cancel the game, and get their bet back, until the opponent's first move
+pub fn cancel(env: &Env, player: Address) {
+    player.require_auth();
+    assert!(has_players(env), "Game is not initialized");
+    assert!(
+        player == get_player_a(env) || player == get_player_b(env),
+        "You are not a player"
+    );
+    assert!(!has_ended(env), "Game has ended");
+    let time = get_time(env);
+    assert!(
+        time == 0 || (time == 1 && get_player_turn(env) != player),
+        "Your opponent has already played"
+    );
+
+    bet::refund(env);
+    set_status(env, GameStatus::Cancelled);
+}
+
+pub fn resign(env: &Env, player: Address) {
+    player.require_auth();
+    assert!(has_players(env), "Game is not initialized");
+    assert!(!has_ended(env), "Game has ended");
+
+    set_winner(env, get_opponent(env, &player));
+    set_status(env, GameStatus::Resigned);
+}
+
+pub fn offer_draw(env: &Env, player: Address) -> bool {
+    let player = session::authorize(env, player, "offer_draw");
+    assert!(has_players(env), "Game is not initialized");
+    assert!(!has_ended(env), "Game has ended");
+
+    let opponent = get_opponent(env, &player);
+    let offer: Option<Address> = env.storage().instance().get(&DataKey::DrawOffer);
+    if offer == Some(opponent) {
+        env.storage().instance().remove(&DataKey::DrawOffer);
+        set_status(env, GameStatus::Draw);
+        return true;
+    }
+    env.storage().instance().set(&DataKey::DrawOffer, &player);
+    false
+}
+
 pub fn grid(env: Env) -> Vec<Symbol> {
+    grid_symbols(&env, get_grid(&env))
+}
+
+pub fn grid_symbols(env: &Env, curr_grid: u32) -> Vec<Symbol> {
     const EMPTY: Symbol = symbol_short!("");
     const X: Symbol = symbol_short!("X");
     const O: Symbol = symbol_short!("O");
-    let mut res = vec![&env];
+    let mut res = vec![env];
     let mut pointer = 0b110000000000000000;
-    let curr_grid = get_grid(&env);
     let mut offset = 16;
     for _ in 0..9 {
         match (curr_grid & pointer) >> offset {
@@ -49,7 +424,42 @@ pub fn winner(env: Env) -> Address {
     get_winner(&env)
 }
 
-fn has_players(env: &Env) -> bool {
+pub fn status(env: &Env) -> GameStatus {
+    env.storage()
+        .instance()
+        .get(&DataKey::Status)
+        .unwrap_or(GameStatus::NotStarted)
+}
+
+pub fn set_status(env: &Env, status: GameStatus) {
+    env.storage().instance().set(&DataKey::Status, &status);
+    if !matches!(status, GameStatus::NotStarted | GameStatus::InProgress) {
+        env.storage()
+            .instance()
+            .set(&DataKey::EndedAt, &env.ledger().timestamp());
+        env.storage()
+            .instance()
+            .set(&DataKey::EndedLedger, &env.ledger().sequence());
+    }
+}
+
+// Timestamp of the ledger the game ended in, 0 while it goes on
+pub fn get_ended_at(env: &Env) -> u64 {
+    env.storage()
+        .instance()
+        .get(&DataKey::EndedAt)
+        .unwrap_or(0)
+}
+
+// Sequence of the ledger the game ended in, 0 while it goes on
+pub fn get_ended_ledger(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::EndedLedger)
+        .unwrap_or(0)
+}
+
+pub fn has_players(env: &Env) -> bool {
     env.storage().instance().has(&DataKey::PlayerA)
         && env.storage().instance().has(&DataKey::PlayerB)
 }
@@ -57,8 +467,6 @@ fn has_players(env: &Env) -> bool {
 fn set_players(env: &Env, player_a: &Address, player_b: &Address) {
     env.storage().instance().set(&DataKey::PlayerA, player_a);
     env.storage().instance().set(&DataKey::PlayerB, player_b);
-
-    env.storage().instance().set(&DataKey::PlayerTurn, player_a);
 }
 
 pub fn get_player_a(env: &Env) -> Address {
@@ -69,8 +477,25 @@ pub fn get_player_b(env: &Env) -> Address {
     env.storage().instance().get(&DataKey::PlayerB).unwrap()
 }
 
+fn get_opponent(env: &Env, player: &Address) -> Address {
+    if *player == get_player_a(env) {
+        get_player_b(env)
+    } else if *player == get_player_b(env) {
+        get_player_a(env)
+    } else {
+        panic!("You are not a player");
+    }
+}
+
 pub fn get_player_turn(env: &Env) -> Address {
-    env.storage().instance().get(&DataKey::PlayerTurn).unwrap()
+    env.storage()
+        .instance()
+        .get(&DataKey::PlayerTurn)
+        .expect("The first player is not decided")
+}
+
+pub fn set_player_turn(env: &Env, player: &Address) {
+    env.storage().instance().set(&DataKey::PlayerTurn, player)
 }
 
 fn change_turn(env: &Env) {
@@ -93,7 +518,7 @@ pub fn get_grid(env: &Env) -> u32 {
         .unwrap_or(0)
 }
 
-fn set_grid(env: &Env, grid: u32) {
+pub fn set_grid(env: &Env, grid: u32) {
     env.storage().instance().set(&DataKey::Grid, &grid)
 }
 
@@ -102,7 +527,7 @@ fn set_grid(env: &Env, grid: u32) {
 // | 2-2 | 2-1 | 2-0 |
 // | 1-2 | 1-1 | 1-0 |
 // | 0-2 | 0-1 | 0-0 |
-fn get_cell_pos(pos_x: u32, pos_y: u32) -> (u32, u32) {
+pub fn get_cell_pos(pos_x: u32, pos_y: u32) -> (u32, u32) {
     let offset = (pos_y * 3 + pos_x) << 1;
     let mask = 0b11 << offset;
     (offset, mask)
@@ -115,20 +540,14 @@ fn is_empty_cell(env: &Env, pos_x: u32, pos_y: u32) -> bool {
     val == 0
 }
 
-fn mark_cell(env: &Env, pos_x: u32, pos_y: u32) {
-    let mut grid = get_grid(env);
-    let (offset, _) = get_cell_pos(pos_x, pos_y);
-
-    if get_player_turn(env) == get_player_a(env) {
-        grid |= 0b01 << offset;
-    } else {
-        grid |= 0b10 << offset;
+pub fn player_mark(env: &Env) -> u32 {
+    match get_player_turn(env) == get_player_a(env) {
+        true => 0b01,
+        false => 0b10,
     }
-
-    set_grid(env, grid);
 }
 
-fn get_time(env: &Env) -> u32 {
+pub fn get_time(env: &Env) -> u32 {
     env.storage()
         .instance()
         .get(&DataKey::Time)
@@ -136,17 +555,24 @@ fn get_time(env: &Env) -> u32 {
 }
 
 fn increase_time(env: &Env) {
-    env.storage()
-        .instance()
-        .set(&DataKey::Time, &(get_time(env) + 1))
+    set_time(env, get_time(env) + 1)
+}
+
+pub fn set_time(env: &Env, time: u32) {
+    env.storage().instance().set(&DataKey::Time, &time)
 }
 
 pub fn has_ended(env: &Env) -> bool {
-    env.storage().instance().has(&DataKey::Winner) || get_time(env) >= 9 || is_expired(env)
+    !matches!(status(env), GameStatus::NotStarted | GameStatus::InProgress)
 }
 
 pub fn has_winner(env: &Env) -> bool {
-    env.storage().instance().has(&DataKey::Winner)
+    match status(env) {
+        GameStatus::Won | GameStatus::Resigned | GameStatus::Forfeited => true,
+        // Adjudicated by the expiry policy
+        GameStatus::Expired => env.storage().instance().has(&DataKey::Winner),
+        _ => false,
+    }
 }
 
 pub fn get_winner(env: &Env) -> Address {
@@ -160,39 +586,45 @@ fn set_winner(env: &Env, winner: Address) {
     env.storage().instance().set(&DataKey::Winner, &winner)
 }
 
-fn check_winner(env: &Env) {
-    let grid = get_grid(env);
-    if grid & 0b010101 == 0b010101
-        || grid & 0b010101000000 == 0b010101000000
-        || grid & 0b010101000000000000 == 0b010101000000000000
-        || grid & 0b010000010000010000 == 0b010000010000010000
-        || grid & 0b000100000100000100 == 0b000100000100000100
-        || grid & 0b000001000001000001 == 0b000001000001000001
-        || grid & 0b010000000100000001 == 0b010000000100000001
-        || grid & 0b000001000100010000 == 0b000001000100010000
-    {
-        set_winner(env, get_player_a(env));
+// Each line is the mask of player_a's three cells, player_b's is shifted by one
+const LINES: [u32; 8] = [
+    0b010101,
+    0b010101000000,
+    0b010101000000000000,
+    0b010000010000010000,
+    0b000100000100000100,
+    0b000001000001000001,
+    0b010000000100000001,
+    0b000001000100010000,
+];
+
+// Returns the mark (0b01 or 0b10) owning a full line, 0 if there is none
+pub fn line_winner(grid: u32) -> u32 {
+    for line in LINES {
+        if grid & line == line {
+            return 0b01;
+        }
+        if grid & (line << 1) == line << 1 {
+            return 0b10;
+        }
     }
+    0
+}
 
-    if grid & 0b101010 == 0b101010
-        || grid & 0b101010000000 == 0b101010000000
-        || grid & 0b101010000000000000 == 0b101010000000000000
-        || grid & 0b100000100000100000 == 0b100000100000100000
-        || grid & 0b001000001000001000 == 0b001000001000001000
-        || grid & 0b000010000010000010 == 0b000010000010000010
-        || grid & 0b100000001000000010 == 0b100000001000000010
-        || grid & 0b000010001000100000 == 0b000010001000100000
-    {
-        set_winner(env, get_player_b(env));
-    }
+pub fn is_full(grid: u32) -> bool {
+    (0..9).all(|cell| (grid >> (cell * 2)) & 0b11 != 0)
 }
 
-fn allowed_player(env: &Env, player: Address) -> bool {
-    player.require_auth();
-    get_player_turn(env) == player
+pub fn set_winner_mark(env: &Env, mark: u32) -> bool {
+    match mark {
+        0b01 => set_winner(env, get_player_a(env)),
+        0b10 => set_winner(env, get_player_b(env)),
+        _ => return false,
+    }
+    true
 }
 
-fn get_expiration(env: &Env) -> u64 {
+pub fn get_expiration(env: &Env) -> u64 {
     env.storage()
         .instance()
         .get(&DataKey::Expiration)
@@ -205,7 +637,7 @@ fn set_expiration(env: &Env, expiration: u64) {
         .set(&DataKey::Expiration, &expiration);
 }
 
-fn is_expired(env: &Env) -> bool {
+pub fn is_expired(env: &Env) -> bool {
     let ledger_timestamp = env.ledger().timestamp();
     let exp_timestamp = get_expiration(env);
     ledger_timestamp >= exp_timestamp
@@ -1,12 +1,31 @@
 #![no_std]
+use crate::analyze::Analysis;
 use crate::bet::Bet;
+use crate::channel::{ChannelState, Dispute};
 use crate::chat::Message;
-use soroban_sdk::{contract, contractimpl, Address, Env, Symbol, Vec};
+use crate::config::{Config, Rules};
+use crate::flip::Flip;
+use crate::game::{GameStatus, Mark};
+use crate::session::Session;
+use crate::signed::SignedMove;
+use crate::state::GameState;
+use soroban_sdk::{contract, contractimpl, Address, BytesN, Env, String, Symbol, Vec};
 
+mod analyze;
 mod bet;
+mod bot;
+mod channel;
 mod chat;
+mod config;
+mod flip;
 mod game;
+mod maybe;
+mod session;
+mod signed;
+mod sliding;
+mod state;
 mod storage;
+mod ultimate;
 
 #[contract]
 pub struct GameContract;
@@ -14,13 +33,153 @@ pub struct GameContract;
 #[contractimpl]
 impl GameContract {
     pub fn init(env: Env, player_a: Address, player_b: Address, expiration: u64) {
-        game::init(env, player_a, player_b, expiration);
+        storage::bump_instance(&env);
+        game::init(env, player_a, player_b, expiration, Config::default());
+    }
+
+    pub fn init_with(
+        env: Env,
+        player_a: Address,
+        player_b: Address,
+        expiration: u64,
+        config: Config,
+    ) {
+        storage::bump_instance(&env);
+        game::init(env, player_a, player_b, expiration, config);
+    }
+
+    pub fn config(env: Env) -> Config {
+        config::get_config(&env)
+    }
+
+    pub fn rules(env: Env) -> Rules {
+        config::get_config(&env).rules
+    }
+
+    pub fn flip_commit(env: Env, player: Address, hash: BytesN<32>) {
+        storage::bump_instance(&env);
+        flip::commit(&env, player, hash)
+    }
+
+    pub fn flip_reveal(env: Env, player: Address, secret: BytesN<32>) -> Option<Address> {
+        storage::bump_instance(&env);
+        flip::reveal(&env, player, secret)
+    }
+
+    pub fn flip_claim(env: Env) {
+        storage::bump_instance(&env);
+        flip::claim(&env)
+    }
+
+    pub fn flip(env: Env) -> Flip {
+        flip::get_flip(&env)
     }
 
     pub fn play(env: Env, player: Address, pos_x: u32, pos_y: u32) -> Vec<Symbol> {
+        storage::bump_instance(&env);
         game::play(env, player, pos_x, pos_y)
     }
 
+    pub fn play_signed(env: Env, signed_move: SignedMove, signature: BytesN<64>) -> Vec<Symbol> {
+        storage::bump_instance(&env);
+        signed::play(env, signed_move, signature)
+    }
+
+    pub fn submit_final_state(
+        env: Env,
+        state: ChannelState,
+        sig_a: BytesN<64>,
+        sig_b: BytesN<64>,
+    ) -> u32 {
+        storage::bump_instance(&env);
+        channel::submit(&env, state, sig_a, sig_b)
+    }
+
+    pub fn settle(env: Env) {
+        storage::bump_instance(&env);
+        channel::settle(&env)
+    }
+
+    pub fn dispute(env: Env) -> Option<Dispute> {
+        channel::get_dispute(&env)
+    }
+
+    pub fn play_mark(
+        env: Env,
+        player: Address,
+        pos_x: u32,
+        pos_y: u32,
+        mark: Mark,
+    ) -> Vec<Symbol> {
+        storage::bump_instance(&env);
+        game::play_mark(env, player, pos_x, pos_y, mark)
+    }
+
+    pub fn move_piece(env: Env, player: Address, from: u32, to: u32) -> Vec<Symbol> {
+        storage::bump_instance(&env);
+        sliding::move_piece(env, player, from, to)
+    }
+
+    pub fn play_ult(
+        env: Env,
+        player: Address,
+        board: u32,
+        pos_x: u32,
+        pos_y: u32,
+    ) -> Vec<Vec<Symbol>> {
+        storage::bump_instance(&env);
+        ultimate::play(env, player, board, pos_x, pos_y)
+    }
+
+    pub fn boards(env: Env) -> Vec<Vec<Symbol>> {
+        ultimate::boards_symbols(&env)
+    }
+
+    pub fn next_board(env: Env) -> Option<u32> {
+        ultimate::get_next_board(&env)
+    }
+
+    pub fn commit(env: Env, player: Address, hash: BytesN<32>) -> u32 {
+        storage::bump_instance(&env);
+        game::commit(&env, player, hash)
+    }
+
+    pub fn reveal(
+        env: Env,
+        player: Address,
+        pos_x: u32,
+        pos_y: u32,
+        salt: BytesN<32>,
+    ) -> Vec<Symbol> {
+        storage::bump_instance(&env);
+        game::reveal(env, player, pos_x, pos_y, salt)
+    }
+
+    pub fn forfeit(env: Env) {
+        storage::bump_instance(&env);
+        game::claim_forfeit(&env)
+    }
+
+    pub fn add_session(
+        env: Env,
+        player: Address,
+        key: Address,
+        expires: u32,
+        scope: Vec<Symbol>,
+    ) {
+        storage::bump_instance(&env);
+        session::add(&env, player, key, expires, scope)
+    }
+
+    pub fn revoke(env: Env, player: Address, key: Address) {
+        storage::bump_instance(&env);
+        session::revoke(&env, player, key)
+    }
+
+    pub fn session(env: Env, key: Address) -> Option<Session> {
+        session::get_session(&env, &key)
+    }
+
     pub fn turn(env: Env) -> Address {
         game::get_player_turn(&env)
     }
@@ -45,25 +204,88 @@ impl GameContract {
         game::has_ended(&env)
     }
 
+    pub fn status(env: Env) -> GameStatus {
+        game::status(&env)
+    }
+
+    pub fn expire(env: Env) {
+        storage::bump_instance(&env);
+        game::expire(&env)
+    }
+
+    pub fn cancel(env: Env, player: Address) {
+        storage::bump_instance(&env);
+        game::cancel(&env, player)
+    }
+
+    pub fn resign(env: Env, player: Address) {
+        storage::bump_instance(&env);
+        game::resign(&env, player)
+    }
+
+    pub fn offer_draw(env: Env, player: Address) -> bool {
+        storage::bump_instance(&env);
+        game::offer_draw(&env, player)
+    }
+
     pub fn grid(env: Env) -> Vec<Symbol> {
         game::grid(env)
     }
 
+    pub fn state(env: Env) -> GameState {
+        state::get_state(&env)
+    }
+
+    pub fn analyze(env: Env) -> Analysis {
+        analyze::analyze(&env)
+    }
+
     pub fn bet(env: Env, player: Address, token: Address, amount: i128) -> Bet {
+        storage::bump_instance(&env);
         bet::make(&env, player, token, amount)
     }
 
     pub fn clct_bet(env: Env, player: Address) -> Vec<Bet> {
+        storage::bump_instance(&env);
         bet::collect(&env, player)
     }
 
-    pub fn send_msg(env: Env, player: Address, message: Symbol) -> Message {
+    pub fn pot(env: Env) -> i128 {
+        bet::get_pot(&env)
+    }
+
+    pub fn clct_pot(env: Env, player: Address) -> i128 {
+        storage::bump_instance(&env);
+        bet::collect_pot(&env, player)
+    }
+
+    pub fn send_msg(env: Env, player: Address, message: String) -> Message {
+        storage::bump_instance(&env);
         chat::add_msg(&env, player, message)
     }
 
     pub fn chat(env: Env) -> Vec<Message> {
         chat::get_chats(&env)
     }
+
+    pub fn chat_page(env: Env, offset: u32, limit: u32) -> Vec<Message> {
+        chat::get_page(&env, offset, limit)
+    }
+
+    pub fn mute(env: Env, player: Address, target: Address) {
+        storage::bump_instance(&env);
+        chat::mute(&env, player, target)
+    }
+
+    pub fn unmute(env: Env, player: Address, target: Address) {
+        storage::bump_instance(&env);
+        chat::unmute(&env, player, target)
+    }
+
+    pub fn extend_ttl(env: Env) {
+        storage::bump_instance(&env);
+        chat::bump(&env);
+    }
 }
 
 mod test;
@@ -0,0 +1,76 @@
+use crate::game::{get_player_a, get_player_b};
+use crate::storage::DataKey;
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+// A key allowed to call some functions on behalf of a player, so the player
+// doesn't have to sign every move. Ed25519 keys are used through their
+// account address.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Session {
+    pub player: Address,
+    // Last ledger the key can be used
+    pub expires: u32,
+    // Names of the functions the key can call
+    pub scope: Vec<Symbol>,
+}
+
+pub fn add(env: &Env, player: Address, key: Address, expires: u32, scope: Vec<Symbol>) {
+    player.require_auth();
+    let player_a = get_player_a(env);
+    let player_b = get_player_b(env);
+    assert!(
+        player == player_a || player == player_b,
+        "Only players can add a session"
+    );
+    assert!(
+        key != player_a && key != player_b,
+        "Session key can't be a player"
+    );
+    assert!(
+        expires >= env.ledger().sequence(),
+        "Session is already expired"
+    );
+    if let Some(session) = get_session(env, &key) {
+        assert!(session.player == player, "Session key is already used");
+    }
+
+    let session = Session {
+        player,
+        expires,
+        scope,
+    };
+    env.storage().instance().set(&DataKey::Session(key), &session);
+}
+
+pub fn revoke(env: &Env, player: Address, key: Address) {
+    player.require_auth();
+    let session = get_session(env, &key).expect("Session doesn't exist");
+    assert!(session.player == player, "Session belongs to another player");
+
+    env.storage().instance().remove(&DataKey::Session(key));
+}
+
+pub fn get_session(env: &Env, key: &Address) -> Option<Session> {
+    env.storage().instance().get(&DataKey::Session(key.clone()))
+}
+
+// Requires the signature of `caller` and returns the player it acts for:
+// the player of its session, or `caller` itself if it isn't a session key
+pub fn authorize(env: &Env, caller: Address, func: &str) -> Address {
+    caller.require_auth();
+    match get_session(env, &caller) {
+        Some(session) => {
+            assert!(
+                env.ledger().sequence() <= session.expires,
+                "Session has expired"
+            );
+            assert!(
+                session.scope.contains(&Symbol::new(env, func)),
+                "Session can't call this function"
+            );
+            session.player
+        }
+        None => caller,
+    }
+}
@@ -0,0 +1,51 @@
+use crate::config::get_config;
+use crate::game::{
+    assert_classic_move, get_player_a, get_player_turn, get_time, grid, has_players,
+    play_checked,
+};
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{contracttype, Address, Bytes, BytesN, Env, Symbol, Vec};
+
+// Move signed off-chain by the player on turn, anyone can submit it
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct SignedMove {
+    pub game: Address,
+    // Number of moves played before this one, so a move can't be replayed
+    pub index: u32,
+    pub pos_x: u32,
+    pub pos_y: u32,
+    pub nonce: u64,
+}
+
+pub fn play(env: Env, signed_move: SignedMove, signature: BytesN<64>) -> Vec<Symbol> {
+    assert!(has_players(&env), "Game is not initialized");
+    assert!(
+        signed_move.game == env.current_contract_address(),
+        "Move is for another game"
+    );
+    assert!(
+        signed_move.index == get_time(&env),
+        "Move was already played"
+    );
+
+    let player = get_player_turn(&env);
+    let payload = signed_move.clone().to_xdr(&env);
+    verify(&env, &player, &payload, &signature);
+
+    let config = assert_classic_move(&env, &player, signed_move.pos_x, signed_move.pos_y);
+    play_checked(&env, &config, signed_move.pos_x, signed_move.pos_y);
+
+    grid(env)
+}
+
+// Panics if `signature` isn't the signature of `payload` by the key of `player`
+pub fn verify(env: &Env, player: &Address, payload: &Bytes, signature: &BytesN<64>) {
+    let config = get_config(env);
+    let key = match *player == get_player_a(env) {
+        true => config.key_a,
+        false => config.key_b,
+    };
+    let key = key.to_option().expect("Player has no signing key");
+    env.crypto().ed25519_verify(&key, payload, signature);
+}
@@ -0,0 +1,84 @@
+use crate::config::{get_config, Variant};
+use crate::game::{
+    self, assert_can_move, end_turn, get_grid, get_time, has_players, line_winner, player_mark,
+    set_grid, set_winner_mark, PIECES,
+};
+use crate::session;
+use crate::storage::DataKey;
+use soroban_sdk::{vec, Address, Env, Symbol, Vec};
+
+// Cells are numbered pos_y * 3 + pos_x, like the offsets of `get_cell_pos`.
+// Pieces move along the lines of the board: to an orthogonal neighbour,
+// or between a corner and the centre.
+const ADJACENT: [u32; 9] = [
+    0b000011010,
+    0b000010101,
+    0b000110010,
+    0b001010001,
+    0b111101111,
+    0b100010100,
+    0b010011000,
+    0b101010000,
+    0b010110000,
+];
+
+// A position seen this many times is a draw
+const REPETITIONS: u32 = 3;
+
+pub fn move_piece(env: Env, player: Address, from: u32, to: u32) -> Vec<Symbol> {
+    assert!(has_players(&env), "Game is not initialized");
+    let config = get_config(&env);
+    assert!(config.variant == Variant::Sliding, "Game is not sliding");
+    assert_can_move(&env, &session::authorize(&env, player, "move_piece"));
+    assert!(get_time(&env) >= PIECES * 2, "Place your pieces first");
+    assert!(from <= 8, "From position out of range");
+    assert!(to <= 8, "To position out of range");
+    assert!(
+        ADJACENT[from as usize] & (1 << to) != 0,
+        "Cells are not adjacent"
+    );
+
+    let mark = player_mark(&env);
+    let mut grid = get_grid(&env);
+    assert!((grid >> (from * 2)) & 0b11 == mark, "It's not your piece");
+    assert!((grid >> (to * 2)) & 0b11 == 0, "Cell is already used");
+
+    grid &= !(0b11 << (from * 2));
+    grid |= mark << (to * 2);
+    set_grid(&env, grid);
+
+    let won = set_winner_mark(&env, line_winner(grid));
+    // A player who can't move any piece loses
+    let blocked = !won && is_blocked(grid, mark ^ 0b11) && set_winner_mark(&env, mark);
+    let repeated = add_position(&env, grid | mark << 18) >= REPETITIONS;
+    end_turn(
+        &env,
+        won || blocked,
+        repeated || get_time(&env) + 1 >= config.move_limit,
+    );
+
+    game::grid(env)
+}
+
+// Whether none of the pieces of `mark` has an adjacent empty cell
+pub fn is_blocked(grid: u32, mark: u32) -> bool {
+    let empty = (0..9)
+        .filter(|cell| (grid >> (cell * 2)) & 0b11 == 0)
+        .fold(0, |cells, cell| cells | 1 << cell);
+    (0..9).all(|cell| (grid >> (cell * 2)) & 0b11 != mark || ADJACENT[cell] & empty == 0)
+}
+
+// Positions also store the mark that just moved, so the same grid with a
+// different player on turn isn't a repetition
+fn add_position(env: &Env, position: u32) -> u32 {
+    let mut positions: Vec<u32> = env
+        .storage()
+        .instance()
+        .get(&DataKey::Positions)
+        .unwrap_or(vec![env]);
+    positions.push_back(position);
+    env.storage()
+        .instance()
+        .set(&DataKey::Positions, &positions);
+    positions.iter().filter(|seen| *seen == position).count() as u32
+}
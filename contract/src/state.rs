@@ -0,0 +1,42 @@
+use crate::bet::get_bets;
+use crate::game::{self, GameStatus};
+use crate::maybe::{MaybeAddress, MaybeBet};
+use crate::storage::DataKey;
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct GameState {
+    pub player_a: MaybeAddress,
+    pub player_b: MaybeAddress,
+    pub turn: MaybeAddress,
+    pub grid: Vec<Symbol>,
+    pub moves: u32,
+    pub status: GameStatus,
+    pub winner: MaybeAddress,
+    pub expiration: u64,
+    // 0 while the game goes on
+    pub ended_at: u64,
+    pub ended_ledger: u32,
+    pub bet_a: MaybeBet,
+    pub bet_b: MaybeBet,
+}
+
+pub fn get_state(env: &Env) -> GameState {
+    let storage = env.storage().instance();
+    let (bet_a, bet_b) = get_bets(env);
+    GameState {
+        player_a: storage.get::<_, Address>(&DataKey::PlayerA).into(),
+        player_b: storage.get::<_, Address>(&DataKey::PlayerB).into(),
+        turn: storage.get::<_, Address>(&DataKey::PlayerTurn).into(),
+        grid: game::grid(env.clone()),
+        moves: game::get_time(env),
+        status: game::status(env),
+        winner: storage.get::<_, Address>(&DataKey::Winner).into(),
+        expiration: game::get_expiration(env),
+        ended_at: game::get_ended_at(env),
+        ended_ledger: game::get_ended_ledger(env),
+        bet_a: bet_a.into(),
+        bet_b: bet_b.into(),
+    }
+}
@@ -1,4 +1,8 @@
-use soroban_sdk::contracttype;
+use soroban_sdk::{contracttype, Address, Env};
+
+pub const DAY_IN_LEDGERS: u32 = 17280;
+pub const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+pub const CHAT_BUMP_AMOUNT: u32 = DAY_IN_LEDGERS;
 
 #[contracttype]
 pub enum DataKey {
@@ -9,7 +13,29 @@ pub enum DataKey {
     Winner,
     Time,
     Expiration,
+    EndedAt,
+    EndedLedger,
     BetPlayerA,
     BetPlayerB,
     Chats,
+    Config,
+    Muted(Address),
+    ChatRate(Address),
+    Pot,
+    PotPaid(Address),
+    Status,
+    DrawOffer,
+    Commit,
+    Boards,
+    NextBoard,
+    ClosedBoards,
+    Positions,
+    Session(Address),
+    Dispute,
+    SettledSeq,
+    Flip,
+}
+
+pub fn bump_instance(env: &Env) {
+    env.storage().instance().bump(INSTANCE_BUMP_AMOUNT);
 }
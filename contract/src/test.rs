@@ -1,12 +1,30 @@
 #![cfg(test)]
+extern crate std;
 
+use crate::analyze::{Analysis, Outcome};
+use crate::channel::ChannelState;
 use crate::chat::Message;
+use crate::config::{Config, ExpiryPolicy, MoveMode, Rules, StartPolicy, Variant};
+use crate::game::{GameStatus, Mark};
+use crate::maybe::{MaybeAddress, MaybeBet};
+use crate::signed::SignedMove;
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+use soroban_sdk::xdr::ToXdr;
 use soroban_sdk::symbol_short;
 
 use super::{GameContract, GameContractClient};
 use soroban_sdk::testutils::{Ledger, LedgerInfo};
-use soroban_sdk::Symbol;
-use soroban_sdk::{testutils::Address as _, vec, Address, Env};
+use soroban_sdk::{Bytes, BytesN, IntoVal, String, Symbol, TryFromVal, Val};
+use soroban_sdk::{testutils::Address as _, token, vec, Address, Env};
+
+// The built contract, only the host calls of the native one are metered
+mod wasm_contract {
+    soroban_sdk::contractimport!(file = "../game/tictactoe_game.wasm");
+}
+
+mod wasm_bot {
+    soroban_sdk::contractimport!(file = "../game/tictactoe_bot.wasm");
+}
 
 struct GameTest<'a> {
     env: Env,
@@ -26,9 +44,9 @@ impl GameTest<'_> {
             sequence_number: 10,
             network_id: Default::default(),
             base_reserve: 10,
-            min_temp_entry_expiration: 1000,
-            min_persistent_entry_expiration: 1000,
-            max_entry_expiration: 1000,
+            min_temp_entry_expiration: 16,
+            min_persistent_entry_expiration: 4096,
+            max_entry_expiration: 6_312_000,
         });
 
         let contract_id = env.register_contract(None, GameContract);
@@ -49,6 +67,96 @@ impl GameTest<'_> {
         }
     }
 
+    fn ultimate_config() -> Config {
+        Config {
+            variant: Variant::Ultimate,
+            ..Config::default()
+        }
+    }
+
+    fn sliding_config(move_limit: u32) -> Config {
+        Config {
+            variant: Variant::Sliding,
+            move_limit,
+            ..Config::default()
+        }
+    }
+
+    // player_a holds cells 0, 2 and 6, player_b holds 1, 3 and 5
+    fn place_sliding_pieces(client: &GameContractClient, player_a: &Address, player_b: &Address) {
+        client.play(&player_a, &0, &0);
+        client.play(&player_b, &1, &0);
+        client.play(&player_a, &2, &0);
+        client.play(&player_b, &0, &1);
+        client.play(&player_a, &0, &2);
+        client.play(&player_b, &2, &1);
+    }
+
+    fn keypair(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    fn public_key(env: &Env, keypair: &Keypair) -> BytesN<32> {
+        BytesN::from_array(env, &keypair.public.to_bytes())
+    }
+
+    fn sign(env: &Env, keypair: &Keypair, payload: Bytes) -> BytesN<64> {
+        let payload: std::vec::Vec<u8> = payload.iter().collect();
+        BytesN::from_array(env, &keypair.sign(&payload).to_bytes())
+    }
+
+    fn signed_config(env: &Env, key_a: &Keypair, key_b: &Keypair) -> Config {
+        Config {
+            key_a: Some(GameTest::public_key(env, key_a)).into(),
+            key_b: Some(GameTest::public_key(env, key_b)).into(),
+            ..Config::default()
+        }
+    }
+
+    fn submit_state(
+        env: &Env,
+        client: &GameContractClient,
+        keys: (&Keypair, &Keypair),
+        state: &ChannelState,
+    ) -> u32 {
+        let payload = state.clone().to_xdr(env);
+        let sig_a = GameTest::sign(env, keys.0, payload.clone());
+        let sig_b = GameTest::sign(env, keys.1, payload);
+        client.submit_final_state(state, &sig_a, &sig_b)
+    }
+
+    fn bot_config() -> Config {
+        Config {
+            bot: true,
+            ..Config::default()
+        }
+    }
+
+    fn rules_config(rules: Rules) -> Config {
+        Config {
+            rules,
+            ..Config::default()
+        }
+    }
+
+    fn commit_config() -> Config {
+        Config {
+            move_mode: MoveMode::CommitReveal,
+            reveal_window: 10,
+            ..Config::default()
+        }
+    }
+
+    fn move_hash(env: &Env, pos_x: u32, pos_y: u32, salt: &BytesN<32>) -> BytesN<32> {
+        let mut data = Bytes::new(env);
+        data.extend_from_array(&pos_x.to_be_bytes());
+        data.extend_from_array(&pos_y.to_be_bytes());
+        data.append(&Bytes::from(salt.clone()));
+        env.crypto().sha256(&data)
+    }
+
     fn make_player_a_win(client: &GameContractClient, player_a: &Address, player_b: &Address) {
         client.play(&player_a, &0, &0);
         client.play(&player_b, &0, &1);
@@ -359,8 +467,12 @@ fn test_expired() {
     } = GameTest::setup();
 
     client.init(&player_a, &player_b, &100);
+    assert_eq!(client.ended(), false);
+
+    client.expire();
 
     assert_eq!(client.ended(), true);
+    assert_eq!(client.has_winner(), false);
 }
 
 #[test]
@@ -392,7 +504,8 @@ fn test_send_message() {
 
     let msg = Message {
         author: player_a,
-        body: symbol_short!("Hello"),
+        body: String::from_slice(&env, "Hello there!"),
+        timestamp: 12345,
     };
     client.send_msg(&msg.author, &msg.body);
 
@@ -413,15 +526,2303 @@ fn test_send_messages() {
 
     let msg = Message {
         author: player_a,
-        body: symbol_short!("Hello"),
+        body: String::from_slice(&env, "Hello"),
+        timestamp: 12345,
     };
     client.send_msg(&msg.author, &msg.body);
 
     let msg2 = Message {
         author: player_b,
-        body: symbol_short!("No"),
+        body: String::from_slice(&env, "No"),
+        timestamp: 12345,
     };
     client.send_msg(&msg2.author, &msg2.body);
 
     assert_eq!(client.chat(), vec![&env, msg, msg2]);
 }
+
+#[test]
+fn test_extend_ttl() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    // The test host records bumps without applying them, so this only checks
+    // extending works with and without a chat, not that anything expires
+    client.init(&player_a, &player_b, &expiration);
+    client.extend_ttl();
+    client.send_msg(&player_a, &String::from_slice(&env, "Hi"));
+    client.extend_ttl();
+
+    assert_eq!(client.player_a(), player_a);
+    assert_eq!(client.chat().len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "You are not allowed to chat")]
+fn test_spectator_message_disabled() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let spectator = Address::random(&env);
+
+    client.init(&player_a, &player_b, &expiration);
+
+    client.send_msg(&spectator, &String::from_slice(&env, "Hi"));
+}
+
+#[test]
+fn test_spectator_message_enabled() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let spectator = Address::random(&env);
+
+    let config = Config {
+        spectators: true,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let msg = client.send_msg(&spectator, &String::from_slice(&env, "Good luck"));
+
+    assert_eq!(msg.author, spectator);
+    assert_eq!(client.chat(), vec![&env, msg]);
+}
+
+#[test]
+#[should_panic(expected = "Message is too long")]
+fn test_message_too_long() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        chat_max_len: 4,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    client.send_msg(&player_a, &String::from_slice(&env, "Hello"));
+}
+
+#[test]
+#[should_panic(expected = "Message is too long")]
+fn test_message_too_long_bytes() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        chat_max_len: 5,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    // 5 characters, 6 bytes
+    client.send_msg(&player_a, &String::from_slice(&env, "Héllo"));
+}
+
+#[test]
+fn test_chat_page() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &expiration);
+
+    let msg = client.send_msg(&player_a, &String::from_slice(&env, "one"));
+    let msg2 = client.send_msg(&player_b, &String::from_slice(&env, "two"));
+    let msg3 = client.send_msg(&player_a, &String::from_slice(&env, "three"));
+
+    assert_eq!(client.chat_page(&0, &2), vec![&env, msg, msg2.clone()]);
+    assert_eq!(client.chat_page(&1, &5), vec![&env, msg2, msg3]);
+    assert_eq!(client.chat_page(&3, &2), vec![&env]);
+}
+
+#[test]
+#[should_panic(expected = "You have been muted")]
+fn test_muted_player() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &expiration);
+
+    client.mute(&player_a, &player_b);
+    client.send_msg(&player_b, &String::from_slice(&env, "Hello"));
+}
+
+#[test]
+fn test_unmuted_player() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &expiration);
+
+    client.mute(&player_a, &player_b);
+    client.unmute(&player_a, &player_b);
+    let msg = client.send_msg(&player_b, &String::from_slice(&env, "Thanks"));
+
+    assert_eq!(client.chat(), vec![&env, msg]);
+}
+
+#[test]
+#[should_panic(expected = "Too many messages")]
+fn test_chat_rate_limit() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        chat_rate: 2,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    client.send_msg(&player_a, &String::from_slice(&env, "one"));
+    client.send_msg(&player_a, &String::from_slice(&env, "two"));
+    client.send_msg(&player_a, &String::from_slice(&env, "three"));
+}
+
+#[test]
+fn test_chat_rate_window() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        chat_rate: 1,
+        chat_window: 5,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    client.send_msg(&player_a, &String::from_slice(&env, "one"));
+    client.send_msg(&player_b, &String::from_slice(&env, "two"));
+    env.ledger().with_mut(|li| li.sequence_number += 5);
+    client.send_msg(&player_a, &String::from_slice(&env, "three"));
+
+    assert_eq!(client.chat().len(), 3);
+}
+
+#[test]
+fn test_chat_cap() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        chat_cap: 2,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    client.send_msg(&player_a, &String::from_slice(&env, "one"));
+    let msg2 = client.send_msg(&player_b, &String::from_slice(&env, "two"));
+    let msg3 = client.send_msg(&player_a, &String::from_slice(&env, "three"));
+
+    assert_eq!(client.chat(), vec![&env, msg2, msg3]);
+}
+
+#[test]
+fn test_chat_fee_pot() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let fee_token = env.register_stellar_asset_contract(Address::random(&env));
+    let token_client = token::Client::new(&env, &fee_token);
+    token::AdminClient::new(&env, &fee_token).mint(&player_b, &100);
+
+    let config = Config {
+        chat_fee: 10,
+        fee_token: Some(fee_token.clone()).into(),
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    client.send_msg(&player_b, &String::from_slice(&env, "gg"));
+    client.send_msg(&player_b, &String::from_slice(&env, "ez"));
+    assert_eq!(token_client.balance(&player_b), 80);
+    assert_eq!(client.pot(), 20);
+
+    GameTest::make_player_a_win(&client, &player_a, &player_b);
+
+    assert_eq!(client.clct_pot(&player_b), 0);
+    assert_eq!(client.clct_pot(&player_a), 20);
+    assert_eq!(token_client.balance(&player_a), 20);
+}
+
+#[test]
+fn test_chat_fee_odd_pot_draw() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let fee_token = env.register_stellar_asset_contract(Address::random(&env));
+    let token_client = token::Client::new(&env, &fee_token);
+    token::AdminClient::new(&env, &fee_token).mint(&player_b, &100);
+
+    let config = Config {
+        chat_fee: 7,
+        fee_token: Some(fee_token.clone()).into(),
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    client.send_msg(&player_b, &String::from_slice(&env, "gg"));
+    client.play(&player_a, &1, &1);
+    client.offer_draw(&player_a);
+    client.offer_draw(&player_b);
+
+    assert_eq!(client.clct_pot(&player_b), 3);
+    assert_eq!(client.clct_pot(&player_a), 4);
+    assert_eq!(token_client.balance(&client.address), 0);
+}
+
+#[test]
+fn test_state_not_initialized() {
+    let GameTest {
+        env: _,
+        player_a: _,
+        player_b: _,
+        expiration: _,
+        client,
+    } = GameTest::setup();
+
+    let state = client.state();
+
+    assert_eq!(state.status, GameStatus::NotStarted);
+    assert_eq!(state.player_a, MaybeAddress::None);
+    assert_eq!(state.turn, MaybeAddress::None);
+    assert_eq!(state.moves, 0);
+}
+
+#[test]
+fn test_state_in_progress() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &expiration);
+    assert_eq!(client.state().status, GameStatus::NotStarted);
+
+    let grid = client.play(&player_a, &1, &1);
+    let state = client.state();
+
+    assert_eq!(state.status, GameStatus::InProgress);
+    assert_eq!(state.player_a, MaybeAddress::Some(player_a));
+    assert_eq!(state.player_b, MaybeAddress::Some(player_b.clone()));
+    assert_eq!(state.turn, MaybeAddress::Some(player_b));
+    assert_eq!(state.grid, grid);
+    assert_eq!(state.moves, 1);
+    assert_eq!(state.winner, MaybeAddress::None);
+    assert_eq!(state.expiration, expiration);
+    assert_eq!(state.ended_at, 0);
+    assert_eq!(state.ended_ledger, 0);
+    assert_eq!(state.bet_a, MaybeBet::None);
+}
+
+#[test]
+fn test_state_won() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &expiration);
+    env.ledger().with_mut(|li| {
+        li.timestamp += 30;
+        li.sequence_number += 5;
+    });
+    GameTest::make_player_a_win(&client, &player_a, &player_b);
+    env.ledger().with_mut(|li| {
+        li.timestamp += 100;
+        li.sequence_number += 20;
+    });
+    let state = client.state();
+
+    assert_eq!(state.status, GameStatus::Won);
+    assert_eq!(state.winner, MaybeAddress::Some(player_a));
+    assert_eq!(state.moves, 5);
+    assert_eq!(state.ended_at, 12345 + 30);
+    assert_eq!(state.ended_ledger, 10 + 5);
+}
+
+#[test]
+fn test_state_expired() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration: _,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &100);
+    client.expire();
+
+    assert_eq!(client.state().status, GameStatus::Expired);
+}
+
+#[test]
+#[should_panic(expected = "Game has expired")]
+fn test_play_expired() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration: _,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &100);
+
+    client.play(&player_a, &0, &0);
+}
+
+#[test]
+#[should_panic(expected = "Game has not expired")]
+fn test_expire_too_early() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &expiration);
+
+    client.expire();
+}
+
+#[test]
+fn test_status_draw() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &expiration);
+
+    client.play(&player_a, &0, &0);
+    client.play(&player_b, &1, &0);
+    client.play(&player_a, &2, &0);
+    client.play(&player_b, &2, &1);
+    client.play(&player_a, &0, &1);
+    client.play(&player_b, &1, &1);
+    client.play(&player_a, &1, &2);
+    client.play(&player_b, &0, &2);
+    assert_eq!(client.status(), GameStatus::InProgress);
+    client.play(&player_a, &2, &2);
+
+    assert_eq!(client.status(), GameStatus::Draw);
+    assert_eq!(client.has_winner(), false);
+}
+
+#[test]
+fn test_resign() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &expiration);
+    client.play(&player_a, &1, &1);
+
+    client.resign(&player_a);
+
+    assert_eq!(client.status(), GameStatus::Resigned);
+    assert_eq!(client.ended(), true);
+    assert_eq!(client.winner(), player_b);
+}
+
+#[test]
+fn test_offer_draw() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &expiration);
+    client.play(&player_a, &1, &1);
+
+    assert_eq!(client.offer_draw(&player_a), false);
+    assert_eq!(client.status(), GameStatus::InProgress);
+    assert_eq!(client.offer_draw(&player_b), true);
+
+    assert_eq!(client.status(), GameStatus::Draw);
+    assert_eq!(client.has_winner(), false);
+}
+
+#[test]
+fn test_draw_offer_cleared_by_move() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &expiration);
+
+    client.offer_draw(&player_a);
+    client.play(&player_a, &1, &1);
+
+    assert_eq!(client.offer_draw(&player_b), false);
+    assert_eq!(client.status(), GameStatus::InProgress);
+}
+
+#[test]
+fn test_commit_reveal() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::commit_config());
+
+    let salt = BytesN::from_array(&env, &[7; 32]);
+    client.commit(&player_a, &GameTest::move_hash(&env, 1, 1, &salt));
+    assert_eq!(client.turn(), player_a);
+
+    let grid = client.reveal(&player_a, &1, &1, &salt);
+
+    assert_eq!(grid.get(4), Some(symbol_short!("X")));
+    assert_eq!(client.turn(), player_b);
+    assert_eq!(client.status(), GameStatus::InProgress);
+}
+
+#[test]
+#[should_panic(expected = "Moves must be committed")]
+fn test_commit_reveal_play() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::commit_config());
+
+    client.play(&player_a, &1, &1);
+}
+
+#[test]
+#[should_panic(expected = "It's not your turn")]
+fn test_commit_out_of_turn() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::commit_config());
+
+    let salt = BytesN::from_array(&env, &[7; 32]);
+    client.commit(&player_b, &GameTest::move_hash(&env, 1, 1, &salt));
+}
+
+#[test]
+fn test_reveal_mismatch_forfeits() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::commit_config());
+
+    let salt = BytesN::from_array(&env, &[7; 32]);
+    client.commit(&player_a, &GameTest::move_hash(&env, 1, 1, &salt));
+    client.reveal(&player_a, &0, &0, &salt);
+
+    assert_eq!(client.status(), GameStatus::Forfeited);
+    assert_eq!(client.winner(), player_b);
+}
+
+#[test]
+fn test_reveal_timeout_forfeits() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::commit_config());
+
+    let salt = BytesN::from_array(&env, &[7; 32]);
+    client.commit(&player_a, &GameTest::move_hash(&env, 1, 1, &salt));
+    env.ledger().with_mut(|li| li.sequence_number += 11);
+    client.forfeit();
+
+    assert_eq!(client.status(), GameStatus::Forfeited);
+    assert_eq!(client.winner(), player_b);
+}
+
+#[test]
+#[should_panic(expected = "Game has expired")]
+fn test_reveal_after_expiry() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::commit_config());
+
+    let salt = BytesN::from_array(&env, &[7; 32]);
+    client.commit(&player_a, &GameTest::move_hash(&env, 1, 1, &salt));
+    env.ledger().with_mut(|li| li.timestamp = expiration);
+    client.reveal(&player_a, &1, &1, &salt);
+}
+
+#[test]
+#[should_panic(expected = "A channel state is being settled")]
+fn test_reveal_during_dispute() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key_a = GameTest::keypair(1);
+    let key_b = GameTest::keypair(2);
+
+    let config = Config {
+        move_mode: MoveMode::CommitReveal,
+        reveal_window: 10,
+        ..GameTest::signed_config(&env, &key_a, &key_b)
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let salt = BytesN::from_array(&env, &[7; 32]);
+    client.commit(&player_a, &GameTest::move_hash(&env, 1, 1, &salt));
+    let state = ChannelState {
+        game: client.address.clone(),
+        seq: 1,
+        grid: 0b0100000000,
+        moves: 1,
+        turn: player_b,
+    };
+    GameTest::submit_state(&env, &client, (&key_a, &key_b), &state);
+
+    client.reveal(&player_a, &1, &1, &salt);
+}
+
+#[test]
+#[should_panic(expected = "Reveal window is still open")]
+fn test_forfeit_too_early() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::commit_config());
+
+    let salt = BytesN::from_array(&env, &[7; 32]);
+    client.commit(&player_a, &GameTest::move_hash(&env, 1, 1, &salt));
+    client.forfeit();
+}
+
+#[test]
+fn test_ultimate_play() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::ultimate_config());
+
+    let boards = client.play_ult(&player_a, &4, &2, &0);
+
+    assert_eq!(boards.get(4).unwrap().get(6), Some(symbol_short!("X")));
+    assert_eq!(client.next_board(), Some(2));
+    assert_eq!(client.turn(), player_b);
+}
+
+#[test]
+#[should_panic(expected = "You must play in the sent board")]
+fn test_ultimate_wrong_board() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::ultimate_config());
+
+    client.play_ult(&player_a, &4, &2, &0);
+    client.play_ult(&player_b, &3, &0, &0);
+}
+
+#[test]
+#[should_panic(expected = "Use play_ult in ultimate games")]
+fn test_ultimate_classic_play() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::ultimate_config());
+
+    client.play(&player_a, &0, &0);
+}
+
+#[test]
+fn test_ultimate_winner() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    env.budget().reset_unlimited();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::ultimate_config());
+
+    // (board, cell) pairs, player_a takes the top row of boards 0, 1 and 2
+    let moves: [(u32, u32); 17] = [
+        (0, 6),
+        (6, 0),
+        (0, 7),
+        (7, 0),
+        (0, 8),
+        (8, 0),
+        (1, 6),
+        (6, 1),
+        (1, 7),
+        (7, 1),
+        (1, 8),
+        (8, 1),
+        (2, 6),
+        (6, 2),
+        (2, 7),
+        (7, 2),
+        (2, 8),
+    ];
+    for (i, (board, cell)) in moves.iter().enumerate() {
+        let player = match i % 2 {
+            0 => &player_a,
+            _ => &player_b,
+        };
+        client.play_ult(player, board, &(cell % 3), &(cell / 3));
+        if i == 4 {
+            assert_eq!(client.grid().get(8), Some(symbol_short!("X")));
+        }
+    }
+
+    assert_eq!(client.status(), GameStatus::Won);
+    assert_eq!(client.winner(), player_a);
+}
+
+#[test]
+fn test_ultimate_free_move_to_closed_board() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    env.budget().reset_unlimited();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::ultimate_config());
+
+    // player_a closes board 0, then both players are sent back to it
+    let moves: [(u32, u32); 7] = [(0, 6), (6, 0), (0, 7), (7, 0), (0, 8), (8, 0), (1, 0)];
+    for (i, (board, cell)) in moves.iter().enumerate() {
+        let player = match i % 2 {
+            0 => &player_a,
+            _ => &player_b,
+        };
+        client.play_ult(player, board, &(cell % 3), &(cell / 3));
+    }
+
+    assert_eq!(client.next_board(), None);
+    assert_eq!(client.turn(), player_b);
+}
+
+#[test]
+fn test_misere() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(
+        &player_a,
+        &player_b,
+        &expiration,
+        &GameTest::rules_config(Rules::Misere),
+    );
+    assert_eq!(client.rules(), Rules::Misere);
+
+    GameTest::make_player_a_win(&client, &player_a, &player_b);
+
+    assert_eq!(client.status(), GameStatus::Won);
+    assert_eq!(client.winner(), player_b);
+}
+
+#[test]
+fn test_wild() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(
+        &player_a,
+        &player_b,
+        &expiration,
+        &GameTest::rules_config(Rules::Wild),
+    );
+
+    client.play_mark(&player_a, &0, &0, &Mark::O);
+    client.play_mark(&player_b, &2, &2, &Mark::X);
+    client.play_mark(&player_a, &1, &0, &Mark::O);
+    let grid = client.play_mark(&player_b, &2, &0, &Mark::O);
+
+    assert_eq!(grid.get(6), Some(symbol_short!("O")));
+    assert_eq!(client.status(), GameStatus::Won);
+    assert_eq!(client.winner(), player_b);
+}
+
+#[test]
+#[should_panic(expected = "Choose a mark with play_mark")]
+fn test_wild_play() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(
+        &player_a,
+        &player_b,
+        &expiration,
+        &GameTest::rules_config(Rules::Wild),
+    );
+
+    client.play(&player_a, &0, &0);
+}
+
+#[test]
+fn test_order_chaos_line() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(
+        &player_a,
+        &player_b,
+        &expiration,
+        &GameTest::rules_config(Rules::OrderChaos),
+    );
+
+    client.play_mark(&player_a, &0, &0, &Mark::X);
+    client.play_mark(&player_b, &1, &0, &Mark::X);
+    client.play_mark(&player_a, &2, &0, &Mark::X);
+
+    assert_eq!(client.winner(), player_a);
+}
+
+#[test]
+fn test_order_chaos_full_board() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(
+        &player_a,
+        &player_b,
+        &expiration,
+        &GameTest::rules_config(Rules::OrderChaos),
+    );
+
+    // X O X / X O O / O X X, from the bottom row up, has no line
+    client.play_mark(&player_a, &0, &0, &Mark::X);
+    client.play_mark(&player_b, &1, &0, &Mark::O);
+    client.play_mark(&player_a, &2, &0, &Mark::X);
+    client.play_mark(&player_b, &0, &1, &Mark::X);
+    client.play_mark(&player_a, &1, &1, &Mark::O);
+    client.play_mark(&player_b, &2, &1, &Mark::O);
+    client.play_mark(&player_a, &0, &2, &Mark::O);
+    client.play_mark(&player_b, &1, &2, &Mark::X);
+    client.play_mark(&player_a, &2, &2, &Mark::X);
+
+    assert_eq!(client.status(), GameStatus::Won);
+    assert_eq!(client.winner(), player_b);
+}
+
+#[test]
+fn test_sliding_win() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::sliding_config(30));
+    GameTest::place_sliding_pieces(&client, &player_a, &player_b);
+
+    let grid = client.move_piece(&player_a, &0, &4);
+
+    assert_eq!(grid.get(8), Some(symbol_short!("")));
+    assert_eq!(grid.get(4), Some(symbol_short!("X")));
+    assert_eq!(client.status(), GameStatus::Won);
+    assert_eq!(client.winner(), player_a);
+}
+
+#[test]
+#[should_panic(expected = "Move a piece with move_piece")]
+fn test_sliding_fourth_piece() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::sliding_config(30));
+    GameTest::place_sliding_pieces(&client, &player_a, &player_b);
+
+    client.play(&player_a, &1, &1);
+}
+
+#[test]
+#[should_panic(expected = "Place your pieces first")]
+fn test_sliding_move_too_early() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::sliding_config(30));
+    client.play(&player_a, &0, &0);
+    client.play(&player_b, &1, &0);
+
+    client.move_piece(&player_a, &0, &4);
+}
+
+#[test]
+#[should_panic(expected = "Cells are not adjacent")]
+fn test_sliding_not_adjacent() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::sliding_config(30));
+    GameTest::place_sliding_pieces(&client, &player_a, &player_b);
+
+    client.move_piece(&player_a, &0, &8);
+}
+
+#[test]
+fn test_sliding_move_limit() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::sliding_config(8));
+    GameTest::place_sliding_pieces(&client, &player_a, &player_b);
+
+    client.move_piece(&player_a, &6, &7);
+    assert_eq!(client.status(), GameStatus::InProgress);
+    client.move_piece(&player_b, &5, &8);
+
+    assert_eq!(client.status(), GameStatus::Draw);
+}
+
+#[test]
+fn test_sliding_blocked() {
+    let mut grid = 0;
+    for cell in [0, 1, 3] {
+        grid |= 0b01 << (cell * 2);
+    }
+    for cell in [2, 4, 6] {
+        grid |= 0b10 << (cell * 2);
+    }
+
+    assert!(crate::sliding::is_blocked(grid, 0b01));
+    assert!(!crate::sliding::is_blocked(grid, 0b10));
+    assert!(!crate::sliding::is_blocked(grid & !(0b11 << 8), 0b01));
+}
+
+#[test]
+fn test_sliding_repetition() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    env.budget().reset_unlimited();
+
+    client.init_with(&player_a, &player_b, &expiration, &GameTest::sliding_config(30));
+    GameTest::place_sliding_pieces(&client, &player_a, &player_b);
+
+    for _ in 0..2 {
+        client.move_piece(&player_a, &6, &7);
+        client.move_piece(&player_b, &5, &8);
+        client.move_piece(&player_a, &7, &6);
+        client.move_piece(&player_b, &8, &5);
+    }
+    assert_eq!(client.status(), GameStatus::InProgress);
+    client.move_piece(&player_a, &6, &7);
+
+    assert_eq!(client.status(), GameStatus::Draw);
+}
+
+#[test]
+fn test_bot_reply() {
+    let GameTest {
+        env,
+        player_a,
+        player_b: _,
+        expiration,
+        client,
+    } = GameTest::setup();
+    env.budget().reset_unlimited();
+    let bot = env.register_contract(None, tictactoe_bot::BotContract);
+
+    client.init_with(&player_a, &bot, &expiration, &GameTest::bot_config());
+
+    let grid = client.play(&player_a, &0, &0);
+
+    assert_eq!(grid.get(4), Some(symbol_short!("O")));
+    assert_eq!(client.turn(), player_a);
+    assert_eq!(client.state().moves, 2);
+}
+
+#[test]
+fn test_bot_blocks() {
+    let GameTest {
+        env,
+        player_a,
+        player_b: _,
+        expiration,
+        client,
+    } = GameTest::setup();
+    env.budget().reset_unlimited();
+    let bot = env.register_contract(None, tictactoe_bot::BotContract);
+
+    client.init_with(&player_a, &bot, &expiration, &GameTest::bot_config());
+
+    client.play(&player_a, &0, &0);
+    let grid = client.play(&player_a, &1, &0);
+
+    assert_eq!(grid.get(6), Some(symbol_short!("O")));
+    assert_eq!(client.status(), GameStatus::InProgress);
+}
+
+#[test]
+fn test_bot_reply_budget() {
+    let GameTest {
+        env,
+        player_a,
+        player_b: _,
+        expiration,
+        client: _,
+    } = GameTest::setup();
+    env.budget().reset_unlimited();
+    let contract_id = env.register_contract_wasm(None, wasm_contract::WASM);
+    let client = wasm_contract::Client::new(&env, &contract_id);
+    let bot = env.register_contract_wasm(None, wasm_bot::WASM);
+
+    let config: Val = GameTest::bot_config().into_val(&env);
+    let config = wasm_contract::Config::try_from_val(&env, &config).unwrap();
+    client.init_with(&player_a, &bot, &expiration, &config);
+
+    // The bot's first reply is the most expensive one to search
+    env.budget().reset_default();
+    client.play(&player_a, &0, &0);
+
+    env.budget().reset_unlimited();
+    assert_eq!(client.state().moves, 2);
+}
+
+#[test]
+#[should_panic(expected = "Bots only play classic games")]
+fn test_bot_misere() {
+    let GameTest {
+        env,
+        player_a,
+        player_b: _,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let bot = env.register_contract(None, tictactoe_bot::BotContract);
+
+    let config = Config {
+        rules: Rules::Misere,
+        ..GameTest::bot_config()
+    };
+    client.init_with(&player_a, &bot, &expiration, &config);
+}
+
+#[test]
+fn test_session_play() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key = Address::random(&env);
+
+    client.init(&player_a, &player_b, &expiration);
+    client.add_session(&player_a, &key, &100, &vec![&env, symbol_short!("play")]);
+
+    let grid = client.play(&key, &1, &1);
+
+    assert_eq!(grid.get(4), Some(symbol_short!("X")));
+    assert_eq!(client.turn(), player_b);
+    assert_eq!(client.session(&key).unwrap().player, player_a);
+}
+
+#[test]
+fn test_session_message() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key = Address::random(&env);
+
+    client.init(&player_a, &player_b, &expiration);
+    let scope = vec![&env, Symbol::new(&env, "send_msg")];
+    client.add_session(&player_b, &key, &100, &scope);
+
+    let msg = client.send_msg(&key, &String::from_slice(&env, "Hello"));
+
+    assert_eq!(msg.author, player_b);
+}
+
+#[test]
+#[should_panic(expected = "Session can't call this function")]
+fn test_session_scope() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key = Address::random(&env);
+
+    client.init(&player_a, &player_b, &expiration);
+    let scope = vec![&env, Symbol::new(&env, "send_msg")];
+    client.add_session(&player_a, &key, &100, &scope);
+
+    client.play(&key, &1, &1);
+}
+
+#[test]
+#[should_panic(expected = "Session has expired")]
+fn test_session_expired() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key = Address::random(&env);
+
+    client.init(&player_a, &player_b, &expiration);
+    client.add_session(&player_a, &key, &20, &vec![&env, symbol_short!("play")]);
+
+    env.ledger().with_mut(|li| li.sequence_number = 21);
+    client.play(&key, &1, &1);
+}
+
+#[test]
+#[should_panic(expected = "It's not your turn")]
+fn test_session_revoked() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key = Address::random(&env);
+
+    client.init(&player_a, &player_b, &expiration);
+    client.add_session(&player_a, &key, &100, &vec![&env, symbol_short!("play")]);
+    client.revoke(&player_a, &key);
+    assert_eq!(client.session(&key), None);
+
+    client.play(&key, &1, &1);
+}
+
+#[test]
+fn test_play_signed() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key_a = GameTest::keypair(1);
+    let key_b = GameTest::keypair(2);
+
+    let config = GameTest::signed_config(&env, &key_a, &key_b);
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let signed_move = SignedMove {
+        game: client.address.clone(),
+        index: 0,
+        pos_x: 1,
+        pos_y: 1,
+        nonce: 42,
+    };
+    let signature = GameTest::sign(&env, &key_a, signed_move.clone().to_xdr(&env));
+    client.play_signed(&signed_move, &signature);
+
+    let signed_move = SignedMove {
+        game: client.address.clone(),
+        index: 1,
+        pos_x: 0,
+        pos_y: 0,
+        nonce: 43,
+    };
+    let signature = GameTest::sign(&env, &key_b, signed_move.clone().to_xdr(&env));
+    let grid = client.play_signed(&signed_move, &signature);
+
+    assert_eq!(grid.get(4), Some(symbol_short!("X")));
+    assert_eq!(grid.get(8), Some(symbol_short!("O")));
+    assert_eq!(client.turn(), player_a);
+}
+
+#[test]
+#[should_panic(expected = "Move was already played")]
+fn test_play_signed_replay() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key_a = GameTest::keypair(1);
+    let key_b = GameTest::keypair(2);
+
+    let config = GameTest::signed_config(&env, &key_a, &key_b);
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let signed_move = SignedMove {
+        game: client.address.clone(),
+        index: 0,
+        pos_x: 1,
+        pos_y: 1,
+        nonce: 42,
+    };
+    let signature = GameTest::sign(&env, &key_a, signed_move.clone().to_xdr(&env));
+    client.play_signed(&signed_move, &signature);
+    client.play_signed(&signed_move, &signature);
+}
+
+#[test]
+#[should_panic]
+fn test_play_signed_wrong_key() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key_a = GameTest::keypair(1);
+    let key_b = GameTest::keypair(2);
+
+    let config = GameTest::signed_config(&env, &key_a, &key_b);
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let signed_move = SignedMove {
+        game: client.address.clone(),
+        index: 0,
+        pos_x: 1,
+        pos_y: 1,
+        nonce: 42,
+    };
+    let signature = GameTest::sign(&env, &key_b, signed_move.clone().to_xdr(&env));
+    client.play_signed(&signed_move, &signature);
+}
+
+#[test]
+fn test_signing_key_needs_player_auth() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        key_b: Some(GameTest::public_key(&env, &GameTest::keypair(2))).into(),
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let auths = env.auths();
+    assert_eq!(auths.len(), 1);
+    assert_eq!(auths[0].0, player_b);
+}
+
+#[test]
+fn test_channel_settle() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key_a = GameTest::keypair(1);
+    let key_b = GameTest::keypair(2);
+
+    let config = GameTest::signed_config(&env, &key_a, &key_b);
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    // player_a holds the bottom row, player_b cells 3 and 4
+    let state = ChannelState {
+        game: client.address.clone(),
+        seq: 5,
+        grid: 0b1010010101,
+        moves: 5,
+        turn: player_b.clone(),
+    };
+    let deadline = GameTest::submit_state(&env, &client, (&key_a, &key_b), &state);
+    assert_eq!(client.dispute().unwrap().state, state);
+
+    env.ledger().with_mut(|li| li.sequence_number = deadline + 1);
+    client.settle();
+
+    assert_eq!(client.status(), GameStatus::Won);
+    assert_eq!(client.winner(), player_a);
+    assert_eq!(client.dispute(), None);
+}
+
+#[test]
+#[should_panic(expected = "Channels are only available in classic games with standard rules")]
+fn test_channel_needs_standard_rules() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key_a = GameTest::keypair(1);
+    let key_b = GameTest::keypair(2);
+
+    let config = Config {
+        rules: Rules::Misere,
+        ..GameTest::signed_config(&env, &key_a, &key_b)
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    // A line of player_a's marks, a loss for player_a under misere rules
+    let state = ChannelState {
+        game: client.address.clone(),
+        seq: 5,
+        grid: 0b1010010101,
+        moves: 5,
+        turn: player_b,
+    };
+    GameTest::submit_state(&env, &client, (&key_a, &key_b), &state);
+}
+
+#[test]
+#[should_panic(expected = "Challenge period is still open")]
+fn test_channel_settle_too_early() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key_a = GameTest::keypair(1);
+    let key_b = GameTest::keypair(2);
+
+    let config = GameTest::signed_config(&env, &key_a, &key_b);
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let state = ChannelState {
+        game: client.address.clone(),
+        seq: 1,
+        grid: 0b0100000000,
+        moves: 1,
+        turn: player_b,
+    };
+    GameTest::submit_state(&env, &client, (&key_a, &key_b), &state);
+    client.settle();
+}
+
+#[test]
+fn test_channel_challenge() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key_a = GameTest::keypair(1);
+    let key_b = GameTest::keypair(2);
+
+    let config = GameTest::signed_config(&env, &key_a, &key_b);
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let state = ChannelState {
+        game: client.address.clone(),
+        seq: 1,
+        grid: 0b0100000000,
+        moves: 1,
+        turn: player_b.clone(),
+    };
+    GameTest::submit_state(&env, &client, (&key_a, &key_b), &state);
+
+    let later_state = ChannelState {
+        game: client.address.clone(),
+        seq: 5,
+        grid: 0b1010010101,
+        moves: 5,
+        turn: player_b,
+    };
+    let deadline = GameTest::submit_state(&env, &client, (&key_a, &key_b), &later_state);
+
+    env.ledger().with_mut(|li| li.sequence_number = deadline + 1);
+    client.settle();
+
+    assert_eq!(client.winner(), player_a);
+}
+
+#[test]
+#[should_panic(expected = "State is not newer")]
+fn test_channel_challenge_older() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key_a = GameTest::keypair(1);
+    let key_b = GameTest::keypair(2);
+
+    let config = GameTest::signed_config(&env, &key_a, &key_b);
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let state = ChannelState {
+        game: client.address.clone(),
+        seq: 5,
+        grid: 0b1010010101,
+        moves: 5,
+        turn: player_b.clone(),
+    };
+    GameTest::submit_state(&env, &client, (&key_a, &key_b), &state);
+
+    let older_state = ChannelState {
+        game: client.address.clone(),
+        seq: 1,
+        grid: 0b0100000000,
+        moves: 1,
+        turn: player_b,
+    };
+    GameTest::submit_state(&env, &client, (&key_a, &key_b), &older_state);
+}
+
+#[test]
+fn test_channel_fallback_to_play() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key_a = GameTest::keypair(1);
+    let key_b = GameTest::keypair(2);
+
+    let config = GameTest::signed_config(&env, &key_a, &key_b);
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let state = ChannelState {
+        game: client.address.clone(),
+        seq: 1,
+        grid: 0b0100000000,
+        moves: 1,
+        turn: player_b.clone(),
+    };
+    let deadline = GameTest::submit_state(&env, &client, (&key_a, &key_b), &state);
+    env.ledger().with_mut(|li| li.sequence_number = deadline + 1);
+    client.settle();
+
+    let grid = client.play(&player_b, &0, &0);
+
+    assert_eq!(grid.get(4), Some(symbol_short!("X")));
+    assert_eq!(grid.get(8), Some(symbol_short!("O")));
+    assert_eq!(client.turn(), player_a);
+    assert_eq!(client.state().moves, 2);
+}
+
+#[test]
+#[should_panic(expected = "State was already settled")]
+fn test_channel_replay_settled() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key_a = GameTest::keypair(1);
+    let key_b = GameTest::keypair(2);
+
+    let config = GameTest::signed_config(&env, &key_a, &key_b);
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let state = ChannelState {
+        game: client.address.clone(),
+        seq: 1,
+        grid: 0b0100000000,
+        moves: 1,
+        turn: player_b,
+    };
+    let deadline = GameTest::submit_state(&env, &client, (&key_a, &key_b), &state);
+    env.ledger().with_mut(|li| li.sequence_number = deadline + 1);
+    client.settle();
+
+    GameTest::submit_state(&env, &client, (&key_a, &key_b), &state);
+}
+
+#[test]
+#[should_panic(expected = "A channel state is being settled")]
+fn test_channel_play_during_dispute() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+    let key_a = GameTest::keypair(1);
+    let key_b = GameTest::keypair(2);
+
+    let config = GameTest::signed_config(&env, &key_a, &key_b);
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let state = ChannelState {
+        game: client.address.clone(),
+        seq: 1,
+        grid: 0b0100000000,
+        moves: 1,
+        turn: player_b,
+    };
+    GameTest::submit_state(&env, &client, (&key_a, &key_b), &state);
+
+    client.play(&player_a, &0, &0);
+}
+
+#[test]
+fn test_cancel() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &expiration);
+
+    client.cancel(&player_b);
+
+    assert_eq!(client.status(), GameStatus::Cancelled);
+    assert_eq!(client.ended(), true);
+    assert_eq!(client.has_winner(), false);
+}
+
+#[test]
+fn test_cancel_after_own_move() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &expiration);
+    client.play(&player_a, &1, &1);
+
+    client.cancel(&player_a);
+
+    assert_eq!(client.status(), GameStatus::Cancelled);
+}
+
+#[test]
+#[should_panic(expected = "Your opponent has already played")]
+fn test_cancel_after_opponent_move() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &expiration);
+    client.play(&player_a, &1, &1);
+
+    client.cancel(&player_b);
+}
+
+#[test]
+fn test_cancel_refunds_bets() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let bet_token = env.register_stellar_asset_contract(Address::random(&env));
+    let token_client = token::Client::new(&env, &bet_token);
+    token::AdminClient::new(&env, &bet_token).mint(&player_a, &100);
+    token::AdminClient::new(&env, &bet_token).mint(&player_b, &100);
+
+    client.init(&player_a, &player_b, &expiration);
+    client.bet(&player_a, &bet_token, &60);
+    client.bet(&player_b, &bet_token, &40);
+    assert_eq!(token_client.balance(&player_a), 40);
+
+    client.cancel(&player_a);
+
+    assert_eq!(token_client.balance(&player_a), 100);
+    assert_eq!(token_client.balance(&player_b), 100);
+    assert_eq!(client.state().bet_a.to_option().unwrap().paid, true);
+}
+
+#[test]
+fn test_start_alternate() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        start: StartPolicy::Alternate,
+        series_game: 1,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+    assert_eq!(client.turn(), player_b);
+
+    client.play(&player_b, &1, &1);
+    let exp = vec![
+        &env,
+        symbol_short!(""),
+        symbol_short!(""),
+        symbol_short!(""),
+        symbol_short!(""),
+        symbol_short!("O"),
+        symbol_short!(""),
+        symbol_short!(""),
+        symbol_short!(""),
+        symbol_short!(""),
+    ];
+    assert_eq!(client.grid(), exp);
+    assert_eq!(client.turn(), player_a);
+}
+
+#[test]
+fn test_start_random() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client: _,
+    } = GameTest::setup();
+
+    let config = Config {
+        start: StartPolicy::Random,
+        ..Config::default()
+    };
+
+    // The draw follows the ledger, so each player starts some of the games
+    let mut a_starts = 0;
+    for _ in 0..16 {
+        env.ledger().with_mut(|li| li.sequence_number += 1);
+        let client = GameContractClient::new(&env, &env.register_contract(None, GameContract));
+        client.init_with(&player_a, &player_b, &expiration, &config);
+
+        let first = client.turn();
+        client.play(&first, &0, &0);
+        if first == player_a {
+            a_starts += 1;
+        }
+    }
+    assert!(a_starts > 0 && a_starts < 16);
+}
+
+#[test]
+fn test_start_coin_flip() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        start: StartPolicy::CoinFlip,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+    assert_eq!(client.state().turn, MaybeAddress::None);
+
+    let secret_a = BytesN::from_array(&env, &[1; 32]);
+    let secret_b = BytesN::from_array(&env, &[2; 32]);
+    let hash_a = env.crypto().sha256(&Bytes::from(secret_a.clone()));
+    let hash_b = env.crypto().sha256(&Bytes::from(secret_b.clone()));
+
+    client.flip_commit(&player_a, &hash_a);
+    client.flip_commit(&player_b, &hash_b);
+    assert_eq!(client.flip_reveal(&player_a, &secret_a), None);
+    assert_eq!(client.flip_reveal(&player_b, &secret_b), Some(player_b.clone()));
+
+    assert_eq!(client.turn(), player_b);
+    client.play(&player_b, &0, &0);
+}
+
+#[test]
+#[should_panic(expected = "The first player is not decided")]
+fn test_start_coin_flip_pending() {
+    let GameTest {
+        env: _,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        start: StartPolicy::CoinFlip,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    client.play(&player_a, &0, &0);
+}
+
+#[test]
+#[should_panic(expected = "Secret doesn't match the commit")]
+fn test_start_coin_flip_wrong_secret() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        start: StartPolicy::CoinFlip,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let secret = BytesN::from_array(&env, &[1; 32]);
+    let hash = env.crypto().sha256(&Bytes::from(secret));
+    client.flip_commit(&player_a, &hash);
+    client.flip_commit(&player_b, &hash);
+
+    client.flip_reveal(&player_a, &BytesN::from_array(&env, &[3; 32]));
+}
+
+#[test]
+fn test_start_coin_flip_not_revealed() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        start: StartPolicy::CoinFlip,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let secret_a = BytesN::from_array(&env, &[1; 32]);
+    let hash_a = env.crypto().sha256(&Bytes::from(secret_a.clone()));
+    let hash_b = env.crypto().sha256(&Bytes::from(BytesN::from_array(&env, &[2; 32])));
+    client.flip_commit(&player_a, &hash_a);
+    client.flip_commit(&player_b, &hash_b);
+    assert_eq!(client.flip().deadline, 10 + 100);
+    client.flip_reveal(&player_a, &secret_a);
+
+    // player_b saw player_a's secret and keeps their own
+    env.ledger().with_mut(|li| li.sequence_number += 101);
+    client.flip_claim();
+
+    assert_eq!(client.status(), GameStatus::Forfeited);
+    assert_eq!(client.winner(), player_a);
+}
+
+#[test]
+fn test_start_coin_flip_none_revealed() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        start: StartPolicy::CoinFlip,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let hash = env.crypto().sha256(&Bytes::from(BytesN::from_array(&env, &[1; 32])));
+    client.flip_commit(&player_a, &hash);
+    client.flip_commit(&player_b, &hash);
+
+    env.ledger().with_mut(|li| li.sequence_number += 101);
+    client.flip_claim();
+
+    assert_eq!(client.status(), GameStatus::NotStarted);
+    assert_eq!(client.turn(), player_a);
+    client.play(&player_a, &0, &0);
+}
+
+#[test]
+#[should_panic(expected = "Reveal window is still open")]
+fn test_start_coin_flip_claim_too_early() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        start: StartPolicy::CoinFlip,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let hash = env.crypto().sha256(&Bytes::from(BytesN::from_array(&env, &[1; 32])));
+    client.flip_commit(&player_a, &hash);
+    client.flip_commit(&player_b, &hash);
+
+    env.ledger().with_mut(|li| li.sequence_number += 100);
+    client.flip_claim();
+}
+
+#[test]
+#[should_panic(expected = "Reveal window is over")]
+fn test_start_coin_flip_reveal_late() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        start: StartPolicy::CoinFlip,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    let secret = BytesN::from_array(&env, &[1; 32]);
+    let hash = env.crypto().sha256(&Bytes::from(secret.clone()));
+    client.flip_commit(&player_a, &hash);
+    client.flip_commit(&player_b, &hash);
+
+    env.ledger().with_mut(|li| li.sequence_number += 101);
+    client.flip_reveal(&player_a, &secret);
+}
+
+#[test]
+fn test_expire_on_move_loses() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        on_expiry: ExpiryPolicy::OnMove,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+    client.play(&player_a, &1, &1);
+
+    env.ledger().with_mut(|li| li.timestamp = expiration);
+    client.expire();
+
+    assert_eq!(client.status(), GameStatus::Expired);
+    assert_eq!(client.has_winner(), true);
+    assert_eq!(client.winner(), player_a);
+}
+
+#[test]
+fn test_expire_not_started() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        on_expiry: ExpiryPolicy::OnMove,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+
+    env.ledger().with_mut(|li| li.timestamp = expiration);
+    client.expire();
+
+    assert_eq!(client.status(), GameStatus::Expired);
+    assert_eq!(client.has_winner(), false);
+}
+
+#[test]
+fn test_expire_draw() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        on_expiry: ExpiryPolicy::Draw,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+    client.play(&player_a, &1, &1);
+
+    env.ledger().with_mut(|li| li.timestamp = expiration);
+    client.expire();
+
+    assert_eq!(client.status(), GameStatus::Draw);
+    assert_eq!(client.has_winner(), false);
+}
+
+#[test]
+fn test_expire_board_adjudication() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        on_expiry: ExpiryPolicy::Board,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+    // Both have a line to complete, player_a is on move
+    client.play(&player_a, &0, &0);
+    client.play(&player_b, &1, &1);
+    client.play(&player_a, &1, &0);
+    client.play(&player_b, &0, &1);
+
+    env.ledger().with_mut(|li| li.timestamp = expiration);
+    client.expire();
+
+    assert_eq!(client.status(), GameStatus::Expired);
+    assert_eq!(client.winner(), player_a);
+}
+
+#[test]
+fn test_expire_board_no_threat_is_draw() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        on_expiry: ExpiryPolicy::Board,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+    // player_a has a single line to complete, player_b can block it
+    client.play(&player_a, &0, &0);
+    client.play(&player_b, &1, &1);
+    client.play(&player_a, &1, &0);
+
+    env.ledger().with_mut(|li| li.timestamp = expiration);
+    client.expire();
+
+    assert_eq!(client.status(), GameStatus::Draw);
+}
+
+#[test]
+fn test_collect_bet_after_expiry() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let bet_token = env.register_stellar_asset_contract(Address::random(&env));
+    let token_client = token::Client::new(&env, &bet_token);
+    token::AdminClient::new(&env, &bet_token).mint(&player_a, &100);
+    token::AdminClient::new(&env, &bet_token).mint(&player_b, &100);
+
+    let config = Config {
+        on_expiry: ExpiryPolicy::OnMove,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+    client.bet(&player_a, &bet_token, &50);
+    client.bet(&player_b, &bet_token, &50);
+    client.play(&player_a, &1, &1);
+
+    // player_b stalls instead of playing
+    env.ledger().with_mut(|li| li.timestamp = expiration);
+    client.clct_bet(&player_a);
+
+    assert_eq!(client.status(), GameStatus::Expired);
+    assert_eq!(token_client.balance(&player_a), 150);
+}
+
+#[test]
+fn test_analyze_win() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &expiration);
+    client.play(&player_a, &0, &0);
+    client.play(&player_b, &1, &0);
+    env.budget().reset_unlimited();
+
+    let analysis = Analysis {
+        outcome: Outcome::XWins,
+        moves: vec![&env, 3, 4, 6],
+    };
+    assert_eq!(client.analyze(), analysis);
+}
+
+#[test]
+fn test_analyze_draw() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &expiration);
+    client.play(&player_a, &0, &0);
+    client.play(&player_b, &1, &1);
+    client.play(&player_a, &2, &2);
+    env.budget().reset_unlimited();
+
+    // Only an edge holds the draw for O
+    let analysis = Analysis {
+        outcome: Outcome::Draw,
+        moves: vec![&env, 1, 3, 5, 7],
+    };
+    assert_eq!(client.analyze(), analysis);
+}
+
+#[test]
+fn test_analyze_won() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    client.init(&player_a, &player_b, &expiration);
+    client.play(&player_a, &0, &0);
+    client.play(&player_b, &0, &1);
+    client.play(&player_a, &1, &0);
+    client.play(&player_b, &1, &1);
+    client.play(&player_a, &2, &0);
+
+    let analysis = Analysis {
+        outcome: Outcome::XWins,
+        moves: vec![&env],
+    };
+    assert_eq!(client.analyze(), analysis);
+}
+
+#[test]
+fn test_expire_solver() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client,
+    } = GameTest::setup();
+
+    let config = Config {
+        on_expiry: ExpiryPolicy::Solver,
+        ..Config::default()
+    };
+    client.init_with(&player_a, &player_b, &expiration, &config);
+    client.play(&player_a, &0, &0);
+    client.play(&player_b, &1, &0);
+
+    // player_a stalls in a won position
+    env.ledger().with_mut(|li| li.timestamp = expiration);
+    env.budget().reset_unlimited();
+    client.expire();
+
+    assert_eq!(client.status(), GameStatus::Expired);
+    assert_eq!(client.winner(), player_a);
+}
+
+#[test]
+fn test_analyze_book() {
+    // Every position with up to 4 marks that a game can reach with `mark` on turn
+    for position in 0..3u32.pow(9) {
+        let (mut grid, mut marks, mut rest) = (0, [0; 3], position);
+        for cell in 0..9 {
+            grid |= (rest % 3) << (cell * 2);
+            marks[(rest % 3) as usize] += 1;
+            rest /= 3;
+        }
+        for mark in [0b01, 0b10] {
+            let (own, other) = (marks[mark as usize], marks[(mark ^ 0b11) as usize]);
+            if own + other > 4 || !(own == other || own + 1 == other) {
+                continue;
+            }
+            assert_eq!(
+                crate::analyze::book_score(grid, mark),
+                Some(crate::analyze::negamax(grid, mark, -1, 1))
+            );
+        }
+    }
+}
+
+#[test]
+fn test_analyze_budget() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client: _,
+    } = GameTest::setup();
+    env.budget().reset_unlimited();
+    let contract_id = env.register_contract_wasm(None, wasm_contract::WASM);
+    let client = wasm_contract::Client::new(&env, &contract_id);
+
+    client.init(&player_a, &player_b, &expiration);
+    client.play(&player_a, &1, &1);
+
+    env.budget().reset_default();
+    let analysis = client.analyze();
+    assert_eq!(analysis.outcome, wasm_contract::Outcome::Draw);
+    assert_eq!(analysis.moves, vec![&env, 0, 2, 6, 8]);
+}
+
+#[test]
+fn test_expire_solver_budget() {
+    let GameTest {
+        env,
+        player_a,
+        player_b,
+        expiration,
+        client: _,
+    } = GameTest::setup();
+    env.budget().reset_unlimited();
+    let contract_id = env.register_contract_wasm(None, wasm_contract::WASM);
+    let client = wasm_contract::Client::new(&env, &contract_id);
+
+    let config = Config {
+        on_expiry: ExpiryPolicy::Solver,
+        ..Config::default()
+    };
+    let config: Val = config.into_val(&env);
+    let config = wasm_contract::Config::try_from_val(&env, &config).unwrap();
+    client.init_with(&player_a, &player_b, &expiration, &config);
+    client.play(&player_a, &0, &0);
+    client.play(&player_b, &1, &0);
+
+    env.ledger().with_mut(|li| li.timestamp = expiration);
+    env.budget().reset_default();
+    client.expire();
+
+    env.budget().reset_unlimited();
+    assert_eq!(client.status(), wasm_contract::GameStatus::Expired);
+    assert_eq!(client.winner(), player_a);
+}
@@ -0,0 +1,93 @@
+use crate::config::{get_config, Variant};
+use crate::game::{
+    assert_can_move, end_turn, get_cell_pos, get_grid, grid_symbols, has_players, is_full,
+    line_winner, player_mark, set_grid, set_winner_mark,
+};
+use crate::session;
+use crate::storage::DataKey;
+use soroban_sdk::{vec, Address, Env, Symbol, Vec};
+
+// Sub-boards are numbered like the cells of a grid (pos_y * 3 + pos_x) and
+// each one uses the same 2 bits per cell encoding as the classic grid.
+// The main grid stores the winner of every sub-board, and boards are listed
+// in the same order as the cells returned by `grid`.
+const ALL_CLOSED: u32 = 0b111111111;
+
+pub fn play(env: Env, player: Address, board: u32, pos_x: u32, pos_y: u32) -> Vec<Vec<Symbol>> {
+    assert!(has_players(&env), "Game is not initialized");
+    assert!(
+        get_config(&env).variant == Variant::Ultimate,
+        "Game is not ultimate"
+    );
+    assert_can_move(&env, &session::authorize(&env, player, "play_ult"));
+    assert!(board <= 8, "Board out of range");
+    assert!(pos_x <= 2, "X position out of range");
+    assert!(pos_y <= 2, "Y position out of range");
+    if let Some(next) = get_next_board(&env) {
+        assert!(board == next, "You must play in the sent board");
+    }
+
+    let mut closed = get_closed(&env);
+    assert!(closed & (1 << board) == 0, "Board is already closed");
+
+    let mut boards = get_boards(&env);
+    let mut sub_grid = boards.get(board).unwrap();
+    let (offset, mask) = get_cell_pos(pos_x, pos_y);
+    assert!(sub_grid & mask == 0, "Cell is already used");
+
+    let mark = player_mark(&env);
+    sub_grid |= mark << offset;
+    boards.set(board, sub_grid);
+    env.storage().instance().set(&DataKey::Boards, &boards);
+
+    let mut won = false;
+    if line_winner(sub_grid) == mark {
+        let (board_offset, _) = get_cell_pos(board % 3, board / 3);
+        let main_grid = get_grid(&env) | mark << board_offset;
+        set_grid(&env, main_grid);
+        closed |= 1 << board;
+        won = set_winner_mark(&env, line_winner(main_grid));
+    } else if is_full(sub_grid) {
+        closed |= 1 << board;
+    }
+    env.storage().instance().set(&DataKey::ClosedBoards, &closed);
+
+    // Removing a missing instance key fails, so only clear a board that was sent
+    let next = pos_y * 3 + pos_x;
+    if closed & (1 << next) == 0 {
+        env.storage().instance().set(&DataKey::NextBoard, &next);
+    } else if get_next_board(&env).is_some() {
+        env.storage().instance().remove(&DataKey::NextBoard);
+    }
+
+    end_turn(&env, won, closed == ALL_CLOSED);
+
+    boards_symbols(&env)
+}
+
+pub fn boards_symbols(env: &Env) -> Vec<Vec<Symbol>> {
+    let boards = get_boards(env);
+    let mut res = vec![env];
+    for board in (0..9).rev() {
+        res.push_back(grid_symbols(env, boards.get(board).unwrap()));
+    }
+    res
+}
+
+pub fn get_next_board(env: &Env) -> Option<u32> {
+    env.storage().instance().get(&DataKey::NextBoard)
+}
+
+fn get_boards(env: &Env) -> Vec<u32> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Boards)
+        .unwrap_or(vec![env, 0, 0, 0, 0, 0, 0, 0, 0, 0])
+}
+
+fn get_closed(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::ClosedBoards)
+        .unwrap_or(0)
+}
@@ -0,0 +1,111 @@
+use crate::game_contract::{self, GameState, GameStatus, Rules, Variant};
+use crate::stats::get_streak;
+use crate::{get_winner, DataKey, Game, PERSISTENT_BUMP_AMOUNT};
+use soroban_sdk::{contracttype, symbol_short, vec, Address, Env, Symbol, Vec};
+
+// Wins in a row needed for the WinStreak badge
+const STREAK_BADGE: u32 = 5;
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Badge {
+    FirstWin,
+    // Won a classic standard game without the opponent's mark in the centre
+    CentreHold,
+    WinStreak,
+    // Drew a game on a full board
+    PerfectDefence,
+    // Won faster than any game before, from its deployment until it ended
+    FastestWin,
+}
+
+// Evaluates the badge rules once a game is finalized and its stats are recorded
+pub fn evaluate(env: &Env, id: &Address, game: &Game, state: &GameState) {
+    if let Some(winner) = get_winner(state) {
+        add(env, &winner, Badge::FirstWin);
+        if get_streak(env, &winner).current == STREAK_BADGE {
+            add(env, &winner, Badge::WinStreak);
+        }
+
+        if state.status == GameStatus::Won {
+            if keeps_marks(env, id) && !loser_holds_centre(state, winner == game.player_a) {
+                add(env, &winner, Badge::CentreHold);
+            }
+            if is_fastest(env, id, state) {
+                add(env, &winner, Badge::FastestWin);
+            }
+        }
+    } else if state.status == GameStatus::Draw && state.moves == 9 {
+        add(env, &game.player_a, Badge::PerfectDefence);
+        add(env, &game.player_b, Badge::PerfectDefence);
+    }
+}
+
+pub fn get(env: &Env, player: &Address) -> Vec<Badge> {
+    let key = DataKey::Badges(player.clone());
+    env.storage().persistent().get(&key).unwrap_or(vec![env])
+}
+
+fn add(env: &Env, player: &Address, badge: Badge) {
+    let mut badges = get(env, player);
+    if badges.contains(badge) {
+        return;
+    }
+    badges.push_back(badge);
+    let key = DataKey::Badges(player.clone());
+    env.storage().persistent().set(&key, &badges);
+    env.storage()
+        .persistent()
+        .bump(&key, PERSISTENT_BUMP_AMOUNT);
+}
+
+// Only classic standard games keep each player's own marks where they were
+// placed: other rules let players place either mark, Ultimate has no single
+// centre and Sliding moves the pieces
+fn keeps_marks(env: &Env, id: &Address) -> bool {
+    let config = game_contract::Client::new(env, id).config();
+    config.variant == Variant::Classic && config.rules == Rules::Standard
+}
+
+// Whether the loser's mark is in the centre of the final grid, the grid lists cells 8 to 0
+fn loser_holds_centre(state: &GameState, winner_is_a: bool) -> bool {
+    let loser_mark: Symbol = match winner_is_a {
+        true => symbol_short!("O"),
+        false => symbol_short!("X"),
+    };
+    state.grid.get(4) == Some(loser_mark)
+}
+
+pub fn record_start(env: &Env, id: &Address) {
+    let key = DataKey::Started(id.clone());
+    env.storage()
+        .persistent()
+        .set(&key, &env.ledger().timestamp());
+    env.storage()
+        .persistent()
+        .bump(&key, PERSISTENT_BUMP_AMOUNT);
+}
+
+// Updates the fastest win when this game beats it, `game` can see the result
+// long after the game ended so its end comes from the game
+fn is_fastest(env: &Env, id: &Address, state: &GameState) -> bool {
+    let started: Option<u64> = env
+        .storage()
+        .persistent()
+        .get(&DataKey::Started(id.clone()));
+    let duration = match started {
+        Some(started) => state.ended_at.saturating_sub(started),
+        None => return false,
+    };
+    let fastest: Option<u64> = env.storage().persistent().get(&DataKey::FastestWin);
+    if matches!(fastest, Some(fastest) if fastest <= duration) {
+        return false;
+    }
+    env.storage()
+        .persistent()
+        .set(&DataKey::FastestWin, &duration);
+    env.storage()
+        .persistent()
+        .bump(&DataKey::FastestWin, PERSISTENT_BUMP_AMOUNT);
+    true
+}
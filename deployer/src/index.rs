@@ -0,0 +1,93 @@
+use crate::{DataKey, PERSISTENT_BUMP_AMOUNT};
+use core::cmp::min;
+use soroban_sdk::{contracttype, vec, Address, Env, IntoVal, Val, Vec};
+
+// A list of addresses kept one entry per address, with the position of each one,
+// so adding or removing an address only touches a few entries however long it gets
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Index {
+    Waiting,
+    Active,
+    PlayerGames(Address),
+    TokenGames(Address),
+    // Players with at least one win
+    Scorers,
+}
+
+pub fn len(env: &Env, index: &Index) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::IndexLen(index.clone()))
+        .unwrap_or(0)
+}
+
+pub fn get(env: &Env, index: &Index, position: u32) -> Address {
+    env.storage()
+        .persistent()
+        .get(&DataKey::IndexItem(index.clone(), position))
+        .unwrap()
+}
+
+pub fn add(env: &Env, index: Index, id: &Address) {
+    let pos_key = DataKey::IndexPos(index.clone(), id.clone());
+    if env.storage().persistent().has(&pos_key) {
+        return;
+    }
+    let position = len(env, &index);
+    set(env, &pos_key, &position);
+    set(env, &DataKey::IndexItem(index.clone(), position), id);
+    set(env, &DataKey::IndexLen(index), &(position + 1));
+}
+
+// The last address takes the place of the removed one, so the order isn't kept
+pub fn remove(env: &Env, index: Index, id: &Address) {
+    let pos_key = DataKey::IndexPos(index.clone(), id.clone());
+    let position: u32 = match env.storage().persistent().get(&pos_key) {
+        Some(position) => position,
+        None => return,
+    };
+    let last = len(env, &index) - 1;
+    if position != last {
+        let moved = get(env, &index, last);
+        set(env, &DataKey::IndexItem(index.clone(), position), &moved);
+        set(env, &DataKey::IndexPos(index.clone(), moved), &position);
+    }
+    env.storage()
+        .persistent()
+        .remove(&DataKey::IndexItem(index.clone(), last));
+    env.storage().persistent().remove(&pos_key);
+    set(env, &DataKey::IndexLen(index), &last);
+}
+
+pub fn page(env: &Env, index: &Index, offset: u32, limit: u32) -> Vec<Address> {
+    let end = min(offset.saturating_add(limit), len(env, index));
+    let mut games = vec![env];
+    for position in offset..end {
+        games.push_back(get(env, index, position));
+    }
+    games
+}
+
+// Keeps the entries listing `id` alive as long as what it lists
+pub fn bump(env: &Env, index: Index, id: &Address) {
+    let pos_key = DataKey::IndexPos(index.clone(), id.clone());
+    let position: u32 = match env.storage().persistent().get(&pos_key) {
+        Some(position) => position,
+        None => return,
+    };
+    for key in [
+        pos_key,
+        DataKey::IndexItem(index.clone(), position),
+        DataKey::IndexLen(index),
+    ] {
+        env.storage()
+            .persistent()
+            .bump(&key, PERSISTENT_BUMP_AMOUNT);
+    }
+}
+
+fn set<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+    env.storage().persistent().set(key, value);
+    env.storage().persistent().bump(key, PERSISTENT_BUMP_AMOUNT);
+}
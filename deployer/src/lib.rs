@@ -1,25 +1,84 @@
 #![no_std]
+use core::cmp::min;
 use soroban_sdk::{
-    contract, contractimpl, contracttype, map, symbol_short, Address, BytesN, Env, IntoVal, Map,
-    Symbol, Val, Vec,
+    contract, contractimpl, contracttype, map, symbol_short, vec, xdr::ToXdr, Address, Bytes,
+    BytesN, Env, IntoVal, Map, String, Symbol, Val, Vec,
 };
 
+const DAY_IN_LEDGERS: u32 = 17280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+// Players a watcher can follow, and games a feed page can reach, so `feed` stays bounded
+const FOLLOWING_CAP: u32 = 20;
+const FEED_CAP: u32 = 100;
+// Watchers a player can have, so deploying a game emits a bounded number of events
+const FOLLOWERS_CAP: u32 = 50;
+
 mod game_contract {
     soroban_sdk::contractimport!(file = "../game/tictactoe_game.wasm");
 }
 
+use badge::Badge;
+use game_contract::{Config, GameState, GameStatus, MaybeAddress, MaybeBet};
+use index::Index;
+use profile::{Entry, Profile};
+use ranking::Board;
+use season::Season;
+use stats::{Record, Streak};
+
+mod badge;
+mod index;
+mod maybe;
+mod profile;
+mod ranking;
+mod season;
+mod stats;
+mod trophy;
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Game {
     pub player_a: Address,
     pub player_b: Address,
     pub ended: bool,
+    pub cancelled: bool,
+    // Deployment order, newer games have higher numbers
+    pub number: u32,
+    pub expiration: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Pair {
+    pub player_a: Address,
+    pub player_b: Address,
 }
 
 #[contracttype]
 pub enum DataKey {
     Games(Address),
-    Scores,
+    Score(Address),
+    IndexLen(Index),
+    IndexItem(Index, u32),
+    IndexPos(Index, Address),
+    Nonce(Pair),
+    Following(Address),
+    Followers(Address),
+    GameCount,
+    Profile(Address),
+    Name(Bytes),
+    Trophy,
+    Started(Address),
+    Badges(Address),
+    Streak(Address),
+    FastestWin,
+    HeadToHead(Pair),
+    Admin,
+    CurrentSeason,
+    Season(u32),
+    SeasonScore(u32, Address),
+    Ranking(Board),
+    Standings(u32),
 }
 
 #[contract]
@@ -34,6 +93,7 @@ impl Deployer {
         init_args: Vec<Val>,
     ) -> Address {
         const INIT_FN: Symbol = symbol_short!("init");
+        bump_instance(&env);
         let deployed_address = env.deployer().with_current_contract(salt).deploy(wasm_hash);
         let _: Val = env.invoke_contract(
             &deployed_address,
@@ -42,21 +102,125 @@ impl Deployer {
         );
 
         let game = create_game(&env, &init_args);
-        set_game(&env, &deployed_address, game);
+        add_game(&env, &deployed_address, game);
 
         deployed_address
     }
 
+    // Same as `deploy`, but the salt comes from the players and their nonce, see `predict_address`
+    pub fn deploy_for(
+        env: Env,
+        wasm_hash: BytesN<32>,
+        player_a: Address,
+        player_b: Address,
+        config: Config,
+    ) -> Address {
+        const INIT_FN: Symbol = symbol_short!("init_with");
+        // Both players agree on the config, or anyone could take their next address with another one
+        player_a.require_auth();
+        player_b.require_auth();
+        bump_instance(&env);
+        let pair = Pair {
+            player_a: player_a.clone(),
+            player_b: player_b.clone(),
+        };
+        let nonce = get_nonce(&env, &pair);
+        let salt = get_salt(&env, &pair, nonce);
+        set_nonce(&env, &pair, nonce + 1);
+        let config = Config {
+            series_game: nonce,
+            ..config
+        };
+
+        let deployed_address = env.deployer().with_current_contract(salt).deploy(wasm_hash);
+        let init_args = (player_a.clone(), player_b.clone()).into_val(&env);
+        let mut init_args = add_exp(&env, init_args);
+        init_args.push_back(config.into_val(&env));
+        let _: Val = env.invoke_contract(&deployed_address, &INIT_FN, init_args);
+
+        let game = new_game(&env, player_a, player_b);
+        add_game(&env, &deployed_address, game);
+
+        deployed_address
+    }
+
+    pub fn follow(env: Env, watcher: Address, player: Address) {
+        watcher.require_auth();
+        bump_instance(&env);
+        assert!(watcher != player, "You can't follow yourself");
+        let key = DataKey::Following(watcher.clone());
+        assert!(
+            get_index(&env, &key).len() < FOLLOWING_CAP,
+            "You follow too many players"
+        );
+        let followers_key = DataKey::Followers(player.clone());
+        assert!(
+            get_index(&env, &followers_key).len() < FOLLOWERS_CAP,
+            "Player has too many followers"
+        );
+        add_to_index(&env, key, &player);
+        add_to_index(&env, followers_key, &watcher);
+    }
+
+    pub fn unfollow(env: Env, watcher: Address, player: Address) {
+        watcher.require_auth();
+        bump_instance(&env);
+        remove_from_index(&env, DataKey::Following(watcher.clone()), &player);
+        remove_from_index(&env, DataKey::Followers(player), &watcher);
+    }
+
+    pub fn following(env: Env, watcher: Address) -> Vec<Address> {
+        get_index(&env, &DataKey::Following(watcher))
+    }
+
+    // Games of the followed players, newest first, up to the last `FEED_CAP`
+    pub fn feed(env: Env, watcher: Address, offset: u32, limit: u32) -> Vec<Address> {
+        get_feed(&env, &watcher, offset, limit)
+    }
+
+    // The number of games already deployed with `deploy_for` for these players
+    pub fn nonce(env: Env, player_a: Address, player_b: Address) -> u32 {
+        get_nonce(&env, &Pair { player_a, player_b })
+    }
+
+    pub fn predict_address(env: Env, player_a: Address, player_b: Address, nonce: u32) -> Address {
+        let salt = get_salt(&env, &Pair { player_a, player_b }, nonce);
+        contract_address(&env, &env.current_contract_address(), salt)
+    }
+
     pub fn game(env: Env, id: Address) -> Game {
         assert!(has_game(&env, &id), "Game doesn't exist");
+        bump_instance(&env);
         let mut game = get_game(&env, &id);
         if !game.ended {
             let client = game_contract::Client::new(&env, &id);
-            if client.ended() {
-                game.ended = true;
-                set_game(&env, &id, game.clone());
-                if client.has_winner() {
-                    add_win(&env, client.winner());
+            let mut state = client.state();
+            // An expired game only gets its result, from the expiry policy, once expired
+            if is_open(&state)
+                && env.ledger().timestamp() >= state.expiration
+                && client.dispute().is_none()
+            {
+                client.expire();
+                state = client.state();
+            }
+            index_tokens(&env, &id, &state);
+            match state.status.clone() {
+                GameStatus::NotStarted => {}
+                GameStatus::InProgress => {
+                    index::remove(&env, Index::Waiting, &id);
+                    index::add(&env, Index::Active, &id);
+                }
+                status => {
+                    game.ended = true;
+                    game.cancelled = status == GameStatus::Cancelled;
+                    set_game(&env, &id, game.clone());
+                    index::remove(&env, Index::Waiting, &id);
+                    index::remove(&env, Index::Active, &id);
+                    if let Some(winner) = get_winner(&state) {
+                        add_win(&env, winner, state.ended_ledger);
+                    }
+                    stats::record(&env, &game, &state);
+                    badge::evaluate(&env, &id, &game, &state);
                 }
             }
         }
@@ -66,68 +230,429 @@ impl Deployer {
     pub fn scores(env: Env) -> Map<Address, u32> {
         get_scores(&env)
     }
+
+    // Contracts have no constructor yet, so the admin proves they deployed the
+    // manager: its address derives from theirs and the salt they deployed with
+    pub fn init(env: Env, admin: Address, salt: BytesN<32>) {
+        assert!(
+            !env.storage().instance().has(&DataKey::Admin),
+            "Already initialized"
+        );
+        admin.require_auth();
+        assert!(
+            contract_address(&env, &admin, salt) == env.current_contract_address(),
+            "Only the deployer can initialize"
+        );
+        bump_instance(&env);
+        env.storage().instance().set(&DataKey::Admin, &admin);
+    }
+
+    pub fn set_admin(env: Env, admin: Address) {
+        bump_instance(&env);
+        get_admin(&env).require_auth();
+        env.storage().instance().set(&DataKey::Admin, &admin);
+    }
+
+    pub fn start_season(
+        env: Env,
+        start: u32,
+        end: u32,
+        prize_token: Option<Address>,
+        prize: i128,
+        top: u32,
+    ) -> u32 {
+        bump_instance(&env);
+        season::start(&env, start, end, prize_token, prize, top)
+    }
+
+    pub fn close_season(env: Env) -> Vec<Entry> {
+        bump_instance(&env);
+        season::close(&env)
+    }
+
+    pub fn season(env: Env, id: u32) -> Season {
+        season::get_season(&env, id)
+    }
+
+    pub fn current_season(env: Env) -> Option<Season> {
+        season::get_current(&env)
+    }
+
+    pub fn season_standings(env: Env, id: u32, offset: u32, limit: u32) -> Vec<Entry> {
+        season::standings(&env, id, offset, limit)
+    }
+
+    pub fn leaderboard(env: Env, offset: u32, limit: u32) -> Vec<Entry> {
+        profile::leaderboard(&env, ranking::get(&env, &Board::AllTime), offset, limit)
+    }
+
+    pub fn set_profile(
+        env: Env,
+        player: Address,
+        name: String,
+        avatar: Option<BytesN<32>>,
+        config: Option<Config>,
+    ) -> Profile {
+        bump_instance(&env);
+        profile::set(&env, player, name, avatar, config)
+    }
+
+    pub fn profile(env: Env, player: Address) -> Option<Profile> {
+        profile::get(&env, &player)
+    }
+
+    pub fn player_by_name(env: Env, name: String) -> Option<Address> {
+        profile::find(&env, &name)
+    }
+
+    // Games where at least one move has been played, as last observed by `game`,
+    // that haven't expired
+    pub fn active(env: Env, offset: u32, limit: u32) -> Vec<Address> {
+        get_open_page(&env, Index::Active, offset, limit)
+    }
+
+    // Games where nobody has played yet that haven't expired
+    pub fn waiting(env: Env, offset: u32, limit: u32) -> Vec<Address> {
+        get_open_page(&env, Index::Waiting, offset, limit)
+    }
+
+    pub fn player_games(env: Env, player: Address, offset: u32, limit: u32) -> Vec<Address> {
+        index::page(&env, &Index::PlayerGames(player), offset, limit)
+    }
+
+    pub fn token_games(env: Env, token: Address, offset: u32, limit: u32) -> Vec<Address> {
+        index::page(&env, &Index::TokenGames(token), offset, limit)
+    }
+
+    pub fn set_trophy(env: Env, trophy: Address) {
+        bump_instance(&env);
+        get_admin(&env).require_auth();
+        trophy::set(&env, trophy)
+    }
+
+    pub fn trophy(env: Env) -> Option<Address> {
+        trophy::get(&env)
+    }
+
+    // The manager has no tournaments, so the admin awards their winners
+    pub fn award_tournament(env: Env, player: Address) -> u32 {
+        bump_instance(&env);
+        get_admin(&env).require_auth();
+        trophy::award_tournament(&env, &player)
+    }
+
+    pub fn badges(env: Env, player: Address) -> Vec<Badge> {
+        badge::get(&env, &player)
+    }
+
+    // Wins, draws and losses of `player_a` against `player_b`
+    pub fn head_to_head(env: Env, player_a: Address, player_b: Address) -> Record {
+        stats::head_to_head(&env, &player_a, &player_b)
+    }
+
+    pub fn streak(env: Env, player: Address) -> Streak {
+        stats::get_streak(&env, &player)
+    }
+
+    pub fn extend_ttl(env: Env, ids: Vec<Address>) {
+        bump_instance(&env);
+        for id in ids.iter() {
+            if has_game(&env, &id) {
+                let game = get_game(&env, &id);
+                bump_score(&env, &game.player_a);
+                bump_score(&env, &game.player_b);
+                index::bump(&env, Index::Waiting, &id);
+                index::bump(&env, Index::Active, &id);
+                let key = DataKey::Games(id);
+                env.storage()
+                    .persistent()
+                    .bump(&key, PERSISTENT_BUMP_AMOUNT);
+            }
+        }
+    }
+}
+
+fn bump_instance(env: &Env) {
+    env.storage().instance().bump(INSTANCE_BUMP_AMOUNT);
 }
 
 fn has_game(env: &Env, id: &Address) -> bool {
     let key = DataKey::Games(id.clone());
-    env.storage().instance().has(&key)
+    env.storage().persistent().has(&key)
 }
 
 fn get_game(env: &Env, id: &Address) -> Game {
     let key = DataKey::Games(id.clone());
-    env.storage().instance().get(&key).unwrap()
+    env.storage().persistent().get(&key).unwrap()
 }
 
 fn create_game(env: &Env, init_args: &Vec<Val>) -> Game {
     let player_a = init_args.get(0).unwrap().into_val(env);
     let player_b = init_args.get(1).unwrap().into_val(env);
-    let game = Game {
+    new_game(env, player_a, player_b)
+}
+
+fn new_game(env: &Env, player_a: Address, player_b: Address) -> Game {
+    let number: u32 = env
+        .storage()
+        .instance()
+        .get(&DataKey::GameCount)
+        .unwrap_or(0);
+    env.storage()
+        .instance()
+        .set(&DataKey::GameCount, &(number + 1));
+    Game {
         player_a,
         player_b,
         ended: false,
-    };
-    game
+        cancelled: false,
+        number,
+        expiration: get_expiration(env),
+    }
+}
+
+fn add_game(env: &Env, id: &Address, game: Game) {
+    index::add(env, Index::Waiting, id);
+    index::add(env, Index::PlayerGames(game.player_a.clone()), id);
+    index::add(env, Index::PlayerGames(game.player_b.clone()), id);
+    notify_followers(env, id, &game);
+    badge::record_start(env, id);
+    set_game(env, id, game);
+}
+
+// One event per watcher of either player, even if they follow both
+fn notify_followers(env: &Env, id: &Address, game: &Game) {
+    let topic = Symbol::new(env, "game_for_followed");
+    let mut watchers = get_index(env, &DataKey::Followers(game.player_a.clone()));
+    for watcher in get_index(env, &DataKey::Followers(game.player_b.clone())).iter() {
+        if !watchers.contains(&watcher) {
+            watchers.push_back(watcher);
+        }
+    }
+    for watcher in watchers.iter() {
+        env.events().publish((topic.clone(), watcher), id.clone());
+    }
+}
+
+// Merges the followed players' games, newest first. Each player's games are
+// in deployment order, so only the newest unread game of each is compared.
+fn get_feed(env: &Env, watcher: &Address, offset: u32, limit: u32) -> Vec<Address> {
+    let end = min(offset.saturating_add(limit), FEED_CAP);
+    let mut lists: Vec<Index> = vec![env];
+    let mut heads: Vec<(u32, u32)> = vec![env];
+    for player in get_index(env, &DataKey::Following(watcher.clone())).iter() {
+        let games = Index::PlayerGames(player);
+        let left = index::len(env, &games);
+        heads.push_back((left, head_number(env, &games, left)));
+        lists.push_back(games);
+    }
+
+    let mut feed = vec![env];
+    let mut last: Option<Address> = None;
+    let mut count = 0;
+    while count < end {
+        let mut newest: Option<(u32, u32)> = None;
+        for (i, (left, number)) in heads.iter().enumerate() {
+            if left > 0 && !matches!(newest, Some((_, best)) if best >= number) {
+                newest = Some((i as u32, number));
+            }
+        }
+        let Some((i, _)) = newest else { break };
+
+        let games = lists.get(i).unwrap();
+        let (left, _) = heads.get(i).unwrap();
+        let id = index::get(env, &games, left - 1);
+        heads.set(i, (left - 1, head_number(env, &games, left - 1)));
+        // A game between two followed players is in both lists
+        if last.as_ref() == Some(&id) {
+            continue;
+        }
+        if count >= offset {
+            feed.push_back(id.clone());
+        }
+        last = Some(id);
+        count += 1;
+    }
+    feed
+}
+
+fn head_number(env: &Env, games: &Index, left: u32) -> u32 {
+    match left {
+        0 => 0,
+        _ => get_game(env, &index::get(env, games, left - 1)).number,
+    }
 }
 
 fn set_game(env: &Env, id: &Address, game: Game) {
     let key = DataKey::Games(id.clone());
-    env.storage().instance().set(&key, &game)
+    env.storage().persistent().set(&key, &game);
+    env.storage()
+        .persistent()
+        .bump(&key, PERSISTENT_BUMP_AMOUNT);
 }
 
-fn add_exp(env: &Env, init_args: Vec<Val>) -> Vec<Val> {
+fn get_expiration(env: &Env) -> u64 {
     let duration = 60 * 10;
-    let expiration = env.ledger().timestamp() + duration;
+    env.ledger().timestamp() + duration
+}
+
+fn add_exp(env: &Env, init_args: Vec<Val>) -> Vec<Val> {
+    let expiration = get_expiration(env);
     let mut init_args_exp = init_args;
     init_args_exp.push_back(expiration.into_val(env));
     init_args_exp
 }
 
-fn get_scores(env: &Env) -> Map<Address, u32> {
-    let default = map![env];
+fn get_nonce(env: &Env, pair: &Pair) -> u32 {
+    let key = DataKey::Nonce(pair.clone());
+    env.storage().persistent().get(&key).unwrap_or(0)
+}
+
+fn set_nonce(env: &Env, pair: &Pair, nonce: u32) {
+    let key = DataKey::Nonce(pair.clone());
+    env.storage().persistent().set(&key, &nonce);
+    env.storage()
+        .persistent()
+        .bump(&key, PERSISTENT_BUMP_AMOUNT);
+}
+
+// sha256(player_a || player_b || nonce), so the same pair never reuses a salt
+fn get_salt(env: &Env, pair: &Pair, nonce: u32) -> BytesN<32> {
+    let mut data = Bytes::new(env);
+    data.append(&pair.player_a.clone().to_xdr(env));
+    data.append(&pair.player_b.clone().to_xdr(env));
+    data.append(&nonce.to_xdr(env));
+    env.crypto().sha256(&data)
+}
+
+// The SDK can't predict addresses yet, so this hashes the same preimage as the
+// host: ENVELOPE_TYPE_CONTRACT_ID, the network, CONTRACT_ID_PREIMAGE_FROM_ADDRESS,
+// the deployer's ScAddress (its ScVal without the tag) and the salt
+fn contract_address(env: &Env, deployer: &Address, salt: BytesN<32>) -> Address {
+    let mut preimage = Bytes::new(env);
+    preimage.extend_from_array(&8u32.to_be_bytes());
+    preimage.append(&env.ledger().network_id().into());
+    preimage.extend_from_array(&0u32.to_be_bytes());
+    preimage.append(&deployer.clone().to_xdr(env).slice(4..));
+    preimage.append(&salt.into());
+    Address::from_contract_id(&env.crypto().sha256(&preimage))
+}
+
+fn get_index(env: &Env, key: &DataKey) -> Vec<Address> {
+    env.storage().persistent().get(key).unwrap_or(vec![env])
+}
+
+fn set_index(env: &Env, key: &DataKey, games: &Vec<Address>) {
+    env.storage().persistent().set(key, games);
+    env.storage().persistent().bump(key, PERSISTENT_BUMP_AMOUNT);
+}
+
+fn add_to_index(env: &Env, key: DataKey, id: &Address) {
+    let mut games = get_index(env, &key);
+    if !games.contains(id) {
+        games.push_back(id.clone());
+        set_index(env, &key, &games);
+    }
+}
+
+fn remove_from_index(env: &Env, key: DataKey, id: &Address) {
+    let mut games = get_index(env, &key);
+    if let Some(index) = games.first_index_of(id) {
+        games.remove(index);
+        set_index(env, &key, &games);
+    }
+}
+
+// Games that expired before anyone played them stay listed until `game` sees them,
+// so the page leaves them out
+fn get_open_page(env: &Env, index: Index, offset: u32, limit: u32) -> Vec<Address> {
+    let now = env.ledger().timestamp();
+    let mut games = vec![env];
+    for id in index::page(env, &index, offset, limit).iter() {
+        if get_game(env, &id).expiration > now {
+            games.push_back(id);
+        }
+    }
+    games
+}
+
+// Bets are placed after the deployment, so the tokens are indexed once `game` sees them
+fn index_tokens(env: &Env, id: &Address, state: &GameState) {
+    for bet in [&state.bet_a, &state.bet_b] {
+        if let MaybeBet::Some(bet) = bet {
+            index::add(env, Index::TokenGames(bet.token.clone()), id);
+        }
+    }
+}
+
+fn is_open(state: &GameState) -> bool {
+    matches!(
+        state.status,
+        GameStatus::NotStarted | GameStatus::InProgress
+    )
+}
+
+// The winner of a finalized game, expired games can have one depending on their policy
+fn get_winner(state: &GameState) -> Option<Address> {
+    match state.status {
+        GameStatus::Won | GameStatus::Resigned | GameStatus::Forfeited | GameStatus::Expired => {
+            match &state.winner {
+                MaybeAddress::Some(winner) => Some(winner.clone()),
+                MaybeAddress::None => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn get_admin(env: &Env) -> Address {
     env.storage()
         .instance()
-        .get(&DataKey::Scores)
-        .unwrap_or(default)
+        .get(&DataKey::Admin)
+        .expect("Admin is not set")
 }
 
-fn add_win(env: &Env, player: Address) {
-    let score = get_score(env, player.clone());
-    set_score(env, player, score + 1);
+fn get_scores(env: &Env) -> Map<Address, u32> {
+    let mut scores = map![env];
+    for player in index::page(env, &Index::Scorers, 0, u32::MAX).iter() {
+        let score = get_score(env, player.clone());
+        scores.set(player, score);
+    }
+    scores
+}
+
+fn add_win(env: &Env, player: Address, ended_ledger: u32) {
+    let score = get_score(env, player.clone()) + 1;
+    set_score(env, player.clone(), score);
+    season::add_win(env, &player, ended_ledger);
+    trophy::award(env, &player, score);
 }
 
 fn get_score(env: &Env, player: Address) -> u32 {
-    let scores = get_scores(env);
-    let score = scores.get(player);
-    match score {
-        Some(val) => val,
-        _ => 0,
-    }
+    env.storage()
+        .persistent()
+        .get(&DataKey::Score(player))
+        .unwrap_or(0)
 }
 
 fn set_score(env: &Env, player: Address, score: u32) {
-    let mut scores = get_scores(env);
-    scores.set(player, score);
-    env.storage().instance().set(&DataKey::Scores, &scores);
+    let key = DataKey::Score(player.clone());
+    env.storage().persistent().set(&key, &score);
+    env.storage()
+        .persistent()
+        .bump(&key, PERSISTENT_BUMP_AMOUNT);
+    index::add(env, Index::Scorers, &player);
+    ranking::update(env, Board::AllTime, &player, score);
+}
+
+fn bump_score(env: &Env, player: &Address) {
+    let key = DataKey::Score(player.clone());
+    if env.storage().persistent().has(&key) {
+        env.storage()
+            .persistent()
+            .bump(&key, PERSISTENT_BUMP_AMOUNT);
+        index::bump(env, Index::Scorers, player);
+    }
 }
 
 mod test;
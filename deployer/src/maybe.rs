@@ -0,0 +1,48 @@
+use crate::game_contract::Config;
+use crate::profile::Profile;
+use soroban_sdk::{contracttype, Address, BytesN};
+
+// Contract types can't have Option fields with this SDK, its test utilities
+// have no Arbitrary impl for Option. Optional fields use these enums instead,
+// like the ones of the game contract.
+macro_rules! maybe {
+    ($name:ident, $($ty:tt)+) => {
+        #[derive(Clone, Debug, Eq, PartialEq)]
+        #[contracttype]
+        pub enum $name {
+            None,
+            Some($($ty)+),
+        }
+
+        impl $name {
+            pub fn to_option(&self) -> Option<$($ty)+> {
+                match self {
+                    $name::None => None,
+                    $name::Some(value) => Some(value.clone()),
+                }
+            }
+
+            pub fn is_some(&self) -> bool {
+                matches!(self, $name::Some(_))
+            }
+
+            pub fn is_none(&self) -> bool {
+                matches!(self, $name::None)
+            }
+        }
+
+        impl From<Option<$($ty)+>> for $name {
+            fn from(value: Option<$($ty)+>) -> Self {
+                match value {
+                    None => $name::None,
+                    Some(value) => $name::Some(value),
+                }
+            }
+        }
+    };
+}
+
+maybe!(MaybeAddress, Address);
+maybe!(MaybeBytes32, BytesN<32>);
+maybe!(MaybeConfig, Config);
+maybe!(MaybeProfile, Profile);
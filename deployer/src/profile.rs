@@ -0,0 +1,113 @@
+use crate::game_contract::Config;
+use crate::maybe::{MaybeBytes32, MaybeConfig, MaybeProfile};
+use crate::ranking::Rank;
+use crate::{DataKey, PERSISTENT_BUMP_AMOUNT};
+use soroban_sdk::{contracttype, Address, Bytes, BytesN, Env, String, Vec};
+
+const NAME_MIN_LEN: u32 = 3;
+const NAME_MAX_LEN: u32 = 20;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    // Hash of the avatar image, the image itself is stored off chain
+    pub avatar: MaybeBytes32,
+    // Config the player likes to play with, for frontends to prefill
+    pub config: MaybeConfig,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Entry {
+    pub player: Address,
+    pub score: u32,
+    pub profile: MaybeProfile,
+}
+
+pub fn set(
+    env: &Env,
+    player: Address,
+    name: String,
+    avatar: Option<BytesN<32>>,
+    config: Option<Config>,
+) -> Profile {
+    player.require_auth();
+    let key = name_key(env, &name);
+    if let Some(owner) = get_owner(env, &key) {
+        assert!(owner == player, "Name is already taken");
+    }
+    if let Some(old) = get(env, &player) {
+        let old_key = name_key(env, &old.name);
+        env.storage().persistent().remove(&DataKey::Name(old_key));
+    }
+
+    set_owner(env, key, &player);
+    let profile = Profile {
+        name,
+        avatar: avatar.into(),
+        config: config.into(),
+    };
+    let profile_key = DataKey::Profile(player);
+    env.storage().persistent().set(&profile_key, &profile);
+    env.storage()
+        .persistent()
+        .bump(&profile_key, PERSISTENT_BUMP_AMOUNT);
+    profile
+}
+
+pub fn get(env: &Env, player: &Address) -> Option<Profile> {
+    let key = DataKey::Profile(player.clone());
+    env.storage().persistent().get(&key)
+}
+
+pub fn find(env: &Env, name: &String) -> Option<Address> {
+    get_owner(env, &name_key(env, name))
+}
+
+// A page of a ranking, the profiles are only read for the players on it
+pub fn leaderboard(env: &Env, ranks: Vec<Rank>, offset: u32, limit: u32) -> Vec<Entry> {
+    let start = offset.min(ranks.len());
+    let end = start.saturating_add(limit).min(ranks.len());
+    let mut entries: Vec<Entry> = Vec::new(env);
+    for rank in ranks.slice(start..end).iter() {
+        entries.push_back(Entry {
+            profile: get(env, &rank.player).into(),
+            player: rank.player,
+            score: rank.score,
+        });
+    }
+    entries
+}
+
+// Names are unique ignoring case, and only use ASCII letters, digits and '_'
+fn name_key(env: &Env, name: &String) -> Bytes {
+    let len = name.len();
+    assert!(
+        (NAME_MIN_LEN..=NAME_MAX_LEN).contains(&len),
+        "Name must have 3 to 20 characters"
+    );
+    let mut buf = [0u8; NAME_MAX_LEN as usize];
+    let buf = &mut buf[..len as usize];
+    name.copy_into_slice(buf);
+    for c in buf.iter_mut() {
+        assert!(
+            c.is_ascii_alphanumeric() || *c == b'_',
+            "Name can only use letters, digits and _"
+        );
+        c.make_ascii_lowercase();
+    }
+    Bytes::from_slice(env, buf)
+}
+
+fn get_owner(env: &Env, key: &Bytes) -> Option<Address> {
+    env.storage().persistent().get(&DataKey::Name(key.clone()))
+}
+
+fn set_owner(env: &Env, key: Bytes, player: &Address) {
+    let key = DataKey::Name(key);
+    env.storage().persistent().set(&key, player);
+    env.storage()
+        .persistent()
+        .bump(&key, PERSISTENT_BUMP_AMOUNT);
+}
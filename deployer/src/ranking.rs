@@ -0,0 +1,57 @@
+use crate::{DataKey, PERSISTENT_BUMP_AMOUNT};
+use soroban_sdk::{contracttype, vec, Address, Env, Vec};
+
+// Players kept in a ranking, so updating and reading one stays bounded
+// however many players score
+pub const RANKING_CAP: u32 = 100;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Board {
+    AllTime,
+    Season(u32),
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Rank {
+    pub player: Address,
+    pub score: u32,
+}
+
+// The top players of a board sorted by score, highest first
+pub fn get(env: &Env, board: &Board) -> Vec<Rank> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Ranking(board.clone()))
+        .unwrap_or(vec![env])
+}
+
+// Moves the player to their new score, behind the players who reached it first.
+// Only the top `RANKING_CAP` players are kept.
+pub fn update(env: &Env, board: Board, player: &Address, score: u32) {
+    let mut ranks = get(env, &board);
+    if let Some(index) = ranks.iter().position(|rank| rank.player == *player) {
+        ranks.remove(index as u32);
+    }
+    let index = ranks
+        .iter()
+        .position(|rank| rank.score < score)
+        .unwrap_or(ranks.len() as usize) as u32;
+    if index >= RANKING_CAP {
+        return;
+    }
+    let rank = Rank {
+        player: player.clone(),
+        score,
+    };
+    ranks.insert(index, rank);
+    if ranks.len() > RANKING_CAP {
+        ranks.pop_back();
+    }
+    let key = DataKey::Ranking(board);
+    env.storage().persistent().set(&key, &ranks);
+    env.storage()
+        .persistent()
+        .bump(&key, PERSISTENT_BUMP_AMOUNT);
+}
@@ -0,0 +1,163 @@
+use crate::maybe::MaybeAddress;
+use crate::profile::{self, Entry};
+use crate::ranking::{self, Board, RANKING_CAP};
+use crate::{get_admin, DataKey, PERSISTENT_BUMP_AMOUNT};
+use soroban_sdk::{contracttype, token, Address, Env, Vec};
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Season {
+    pub id: u32,
+    // Wins are counted from the start ledger to the end ledger, both included
+    pub start: u32,
+    pub end: u32,
+    pub prize_token: MaybeAddress,
+    pub prize: i128,
+    // Players sharing the prize, at most `RANKING_CAP`
+    pub top: u32,
+    pub closed: bool,
+}
+
+pub fn start(
+    env: &Env,
+    start: u32,
+    end: u32,
+    prize_token: Option<Address>,
+    prize: i128,
+    top: u32,
+) -> u32 {
+    let admin = get_admin(env);
+    admin.require_auth();
+    if let Some(season) = get_current(env) {
+        assert!(season.closed, "The current season is not closed");
+    }
+    assert!(end >= start, "Season ends before it starts");
+    assert!(prize >= 0, "Prize can't be negative");
+    assert!(
+        prize == 0 || (prize_token.is_some() && top > 0),
+        "Prize requires a token and winners"
+    );
+    assert!(top <= RANKING_CAP, "Too many winners");
+
+    if let Some(prize_token) = &prize_token {
+        let contract = env.current_contract_address();
+        token::Client::new(env, prize_token).transfer(&admin, &contract, &prize);
+    }
+    let id = get_current(env).map_or(0, |season| season.id + 1);
+    let season = Season {
+        id,
+        start,
+        end,
+        prize_token: prize_token.into(),
+        prize,
+        top,
+        closed: false,
+    };
+    set_season(env, &season);
+    env.storage().instance().set(&DataKey::CurrentSeason, &id);
+    id
+}
+
+// Anyone can close the season after its end, the standings of the ranked
+// players are archived and the prize is split between the top ones
+pub fn close(env: &Env) -> Vec<Entry> {
+    let mut season = get_current(env).expect("There is no season");
+    assert!(!season.closed, "Season is already closed");
+    assert!(env.ledger().sequence() > season.end, "Season has not ended");
+
+    let standings = profile::leaderboard(
+        env,
+        ranking::get(env, &Board::Season(season.id)),
+        0,
+        RANKING_CAP,
+    );
+    let key = DataKey::Standings(season.id);
+    env.storage().persistent().set(&key, &standings);
+    env.storage()
+        .persistent()
+        .bump(&key, PERSISTENT_BUMP_AMOUNT);
+    season.closed = true;
+    set_season(env, &season);
+
+    if let Some(prize_token) = season.prize_token.to_option() {
+        pay_prize(env, &season, &prize_token, &standings);
+    }
+    standings
+}
+
+// The prize is split evenly, the remainder goes to the first player.
+// Without players it goes back to the admin.
+fn pay_prize(env: &Env, season: &Season, prize_token: &Address, standings: &Vec<Entry>) {
+    let client = token::Client::new(env, prize_token);
+    let contract = env.current_contract_address();
+    let winners = season.top.min(standings.len());
+    if winners == 0 {
+        client.transfer(&contract, &get_admin(env), &season.prize);
+        return;
+    }
+    let share = season.prize / winners as i128;
+    let remainder = season.prize - share * winners as i128;
+    for (index, entry) in standings.iter().take(winners as usize).enumerate() {
+        let amount = match index {
+            0 => share + remainder,
+            _ => share,
+        };
+        client.transfer(&contract, &entry.player, &amount);
+    }
+}
+
+// Wins go to the current season when the game ended within its range, however
+// late `game` sees them, as long as the season isn't closed
+pub fn add_win(env: &Env, player: &Address, ended_ledger: u32) {
+    let season = match get_current(env) {
+        Some(season) => season,
+        None => return,
+    };
+    if season.closed || ended_ledger < season.start || ended_ledger > season.end {
+        return;
+    }
+
+    let key = DataKey::SeasonScore(season.id, player.clone());
+    let score: u32 = env.storage().persistent().get(&key).unwrap_or(0) + 1;
+    env.storage().persistent().set(&key, &score);
+    env.storage()
+        .persistent()
+        .bump(&key, PERSISTENT_BUMP_AMOUNT);
+    ranking::update(env, Board::Season(season.id), player, score);
+}
+
+pub fn get_current(env: &Env) -> Option<Season> {
+    let id: Option<u32> = env.storage().instance().get(&DataKey::CurrentSeason);
+    id.map(|id| get_season(env, id))
+}
+
+pub fn get_season(env: &Env, id: u32) -> Season {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Season(id))
+        .expect("Season doesn't exist")
+}
+
+fn set_season(env: &Env, season: &Season) {
+    let key = DataKey::Season(season.id);
+    env.storage().persistent().set(&key, season);
+    env.storage()
+        .persistent()
+        .bump(&key, PERSISTENT_BUMP_AMOUNT);
+}
+
+// Archived standings of a closed season, the live ranking of an open one
+pub fn standings(env: &Env, id: u32, offset: u32, limit: u32) -> Vec<Entry> {
+    let season = get_season(env, id);
+    if !season.closed {
+        return profile::leaderboard(env, ranking::get(env, &Board::Season(id)), offset, limit);
+    }
+    let standings: Vec<Entry> = env
+        .storage()
+        .persistent()
+        .get(&DataKey::Standings(id))
+        .unwrap();
+    let start = offset.min(standings.len());
+    let end = start.saturating_add(limit).min(standings.len());
+    standings.slice(start..end)
+}
@@ -0,0 +1,109 @@
+use crate::game_contract::{GameState, GameStatus};
+use crate::{get_winner, DataKey, Game, Pair, PERSISTENT_BUMP_AMOUNT};
+use soroban_sdk::{contracttype, Address, Env};
+
+// Results from the point of view of one player
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Record {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Streak {
+    pub current: u32,
+    pub best: u32,
+}
+
+// Updates head to head records and streaks once a game is finalized
+pub fn record(env: &Env, game: &Game, state: &GameState) {
+    if let Some(winner) = get_winner(state) {
+        let loser = match winner == game.player_a {
+            true => game.player_b.clone(),
+            false => game.player_a.clone(),
+        };
+        update_record(env, &winner, &loser, |record| record.wins += 1);
+
+        let mut streak = get_streak(env, &winner);
+        streak.current += 1;
+        streak.best = streak.best.max(streak.current);
+        set_streak(env, &winner, &streak);
+        reset_streak(env, &loser);
+    } else if state.status == GameStatus::Draw {
+        update_record(env, &game.player_a, &game.player_b, |record| {
+            record.draws += 1
+        });
+        reset_streak(env, &game.player_a);
+        reset_streak(env, &game.player_b);
+    }
+}
+
+// Record of `player` against `opponent`
+pub fn head_to_head(env: &Env, player: &Address, opponent: &Address) -> Record {
+    let (pair, flipped) = ordered_pair(player, opponent);
+    let record: Record = env
+        .storage()
+        .persistent()
+        .get(&DataKey::HeadToHead(pair))
+        .unwrap_or_default();
+    match flipped {
+        true => flip(record),
+        false => record,
+    }
+}
+
+fn update_record(env: &Env, player: &Address, opponent: &Address, update: impl Fn(&mut Record)) {
+    let mut record = head_to_head(env, player, opponent);
+    update(&mut record);
+
+    let (pair, flipped) = ordered_pair(player, opponent);
+    let record = match flipped {
+        true => flip(record),
+        false => record,
+    };
+    let key = DataKey::HeadToHead(pair);
+    env.storage().persistent().set(&key, &record);
+    env.storage()
+        .persistent()
+        .bump(&key, PERSISTENT_BUMP_AMOUNT);
+}
+
+// Records are stored once per pair, from the point of view of the lowest address
+fn ordered_pair(player: &Address, opponent: &Address) -> (Pair, bool) {
+    let flipped = player > opponent;
+    let (player_a, player_b) = match flipped {
+        true => (opponent.clone(), player.clone()),
+        false => (player.clone(), opponent.clone()),
+    };
+    (Pair { player_a, player_b }, flipped)
+}
+
+fn flip(record: Record) -> Record {
+    Record {
+        wins: record.losses,
+        draws: record.draws,
+        losses: record.wins,
+    }
+}
+
+pub fn get_streak(env: &Env, player: &Address) -> Streak {
+    let key = DataKey::Streak(player.clone());
+    env.storage().persistent().get(&key).unwrap_or_default()
+}
+
+fn set_streak(env: &Env, player: &Address, streak: &Streak) {
+    let key = DataKey::Streak(player.clone());
+    env.storage().persistent().set(&key, streak);
+    env.storage()
+        .persistent()
+        .bump(&key, PERSISTENT_BUMP_AMOUNT);
+}
+
+fn reset_streak(env: &Env, player: &Address) {
+    let mut streak = get_streak(env, player);
+    streak.current = 0;
+    set_streak(env, player, &streak);
+}
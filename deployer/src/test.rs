@@ -1,12 +1,18 @@
 #![cfg(test)]
 
+use crate::badge::Badge;
+use crate::maybe::MaybeBytes32;
+use crate::ranking::{self, Board, RANKING_CAP};
 use crate::{Deployer, DeployerClient};
-use soroban_sdk::{map, testutils::Address as _, Address, BytesN, Env, IntoVal};
+use soroban_sdk::testutils::{Events, Ledger};
+use soroban_sdk::{
+    map, testutils::Address as _, token, vec, Address, BytesN, Env, IntoVal, String, Symbol, Val,
+    Vec,
+};
 
-// The contract that will be deployed by the deployer contract.
-mod contract {
-    soroban_sdk::contractimport!(file = "../game/tictactoe_game.wasm");
-}
+// The contract that will be deployed by the deployer contract, the same
+// import as the deployer's so configs can be passed to `deploy_for`.
+use crate::game_contract as contract;
 
 struct GameTest<'a> {
     env: Env,
@@ -21,11 +27,18 @@ impl GameTest<'_> {
     fn setup() -> Self {
         let env = Env::default();
         env.mock_all_auths();
-        let deployer_client = DeployerClient::new(&env, &env.register_contract(None, Deployer));
+        // Every call instantiates the game wasm, more than the default budget covers
+        env.budget().reset_unlimited();
+        // Registered where the admin deploying it with this salt would put it
+        let admin = Address::random(&env);
+        let salt = BytesN::from_array(&env, &[0; 32]);
+        let deployer_id = crate::contract_address(&env, &admin, salt.clone());
+        let deployer_client =
+            DeployerClient::new(&env, &env.register_contract(Some(&deployer_id), Deployer));
+        deployer_client.init(&admin, &salt);
 
         let wasm_hash = env.deployer().upload_contract_wasm(contract::WASM);
 
-        let salt = BytesN::from_array(&env, &[0; 32]);
         let player_a = Address::random(&env);
         let player_b = Address::random(&env);
         let init_fn_args = (player_a.clone(), player_b.clone()).into_val(&env);
@@ -93,6 +106,9 @@ fn test_get_game() {
         player_a,
         player_b,
         ended: false,
+        cancelled: false,
+        number: 0,
+        expiration: 600,
     };
 
     assert_eq!(deployer_client.game(&contract_id), game);
@@ -114,6 +130,9 @@ fn test_set_ended() {
         player_a: player_a.clone(),
         player_b: player_b.clone(),
         ended: false,
+        cancelled: false,
+        number: 0,
+        expiration: 600,
     };
 
     assert_eq!(deployer_client.game(&contract_id), game);
@@ -153,6 +172,9 @@ fn test_scores_add_win() {
         player_a: player_a.clone(),
         player_b: player_b.clone(),
         ended: false,
+        cancelled: false,
+        number: 0,
+        expiration: 600,
     };
 
     assert_eq!(deployer_client.game(&contract_id), game);
@@ -221,3 +243,1031 @@ fn test_scores_add_wins_2() {
     ];
     assert_eq!(game_test.deployer_client.scores(), exp);
 }
+
+#[test]
+fn test_extend_ttl() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b,
+        contract_id,
+        game_client,
+    } = GameTest::setup();
+
+    GameTest::make_player_a_win(&game_client, player_a.clone(), player_b.clone());
+    env.budget().reset_unlimited();
+    deployer_client.game(&contract_id);
+    // The test host records bumps without applying them, so this only checks
+    // extending skips addresses that aren't games, not that anything expires
+    deployer_client.extend_ttl(&vec![&env, contract_id.clone(), Address::random(&env)]);
+
+    let game = crate::Game {
+        player_a: player_a.clone(),
+        player_b,
+        ended: true,
+        cancelled: false,
+        number: 0,
+        expiration: 600,
+    };
+    assert_eq!(deployer_client.game(&contract_id), game);
+    assert_eq!(deployer_client.scores(), map![&env, (player_a, 1)]);
+}
+
+#[test]
+fn test_cancelled() {
+    let GameTest {
+        env: _,
+        deployer_client,
+        player_a,
+        player_b,
+        contract_id,
+        game_client,
+    } = GameTest::setup();
+
+    game_client.cancel(&player_b);
+
+    let game = crate::Game {
+        player_a,
+        player_b,
+        ended: true,
+        cancelled: true,
+        number: 0,
+        expiration: 600,
+    };
+    assert_eq!(deployer_client.game(&contract_id), game);
+    assert_eq!(deployer_client.scores().len(), 0);
+}
+
+#[test]
+fn test_indexes() {
+    let game_test = GameTest::setup();
+    let env = &game_test.env;
+    let deployer_client = &game_test.deployer_client;
+
+    let game1 = game_test.deploy_new_game(BytesN::from_array(env, &[1; 32]));
+    env.budget().reset_unlimited();
+    assert_eq!(
+        deployer_client.waiting(&0, &10),
+        vec![env, game_test.contract_id.clone(), game1.address.clone()]
+    );
+    assert_eq!(deployer_client.active(&0, &10), vec![env]);
+
+    game1.play(&game_test.player_a, &1, &1);
+    deployer_client.game(&game1.address);
+    assert_eq!(
+        deployer_client.waiting(&0, &10),
+        vec![env, game_test.contract_id.clone()]
+    );
+    assert_eq!(
+        deployer_client.active(&0, &10),
+        vec![env, game1.address.clone()]
+    );
+
+    game1.resign(&game_test.player_b);
+    deployer_client.game(&game1.address);
+    assert_eq!(deployer_client.active(&0, &10), vec![env]);
+
+    let games = vec![env, game_test.contract_id.clone(), game1.address.clone()];
+    assert_eq!(
+        deployer_client.player_games(&game_test.player_a, &0, &10),
+        games
+    );
+    assert_eq!(
+        deployer_client.player_games(&game_test.player_b, &0, &10),
+        games
+    );
+    assert_eq!(
+        deployer_client.player_games(&game_test.player_a, &1, &10),
+        vec![env, game1.address.clone()]
+    );
+    assert_eq!(
+        deployer_client.player_games(&game_test.player_a, &0, &1),
+        vec![env, game_test.contract_id.clone()]
+    );
+    assert_eq!(
+        deployer_client.player_games(&game_test.player_a, &5, &10),
+        vec![env]
+    );
+}
+
+#[test]
+fn test_indexes_swap_and_expire() {
+    let game_test = GameTest::setup();
+    let env = &game_test.env;
+    let deployer_client = &game_test.deployer_client;
+
+    let game1 = game_test.deploy_new_game(BytesN::from_array(env, &[1; 32]));
+    let game2 = game_test.deploy_new_game(BytesN::from_array(env, &[2; 32]));
+    env.budget().reset_unlimited();
+
+    // The last game takes the place of the one that left
+    game_test.game_client.play(&game_test.player_a, &1, &1);
+    deployer_client.game(&game_test.contract_id);
+    assert_eq!(
+        deployer_client.waiting(&0, &10),
+        vec![env, game2.address.clone(), game1.address.clone()]
+    );
+    game2.play(&game_test.player_a, &1, &1);
+    deployer_client.game(&game2.address);
+    assert_eq!(
+        deployer_client.active(&0, &10),
+        vec![env, game_test.contract_id.clone(), game2.address.clone()]
+    );
+
+    // Expired games are left out before anyone looks at them
+    env.ledger().with_mut(|li| li.timestamp += 60 * 10);
+    assert_eq!(deployer_client.waiting(&0, &10), vec![env]);
+    assert_eq!(deployer_client.active(&0, &10), vec![env]);
+
+    assert!(deployer_client.game(&game1.address).ended);
+}
+
+#[test]
+fn test_token_games() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b: _,
+        contract_id,
+        game_client,
+    } = GameTest::setup();
+
+    let bet_token = env.register_stellar_asset_contract(Address::random(&env));
+    token::AdminClient::new(&env, &bet_token).mint(&player_a, &100);
+
+    game_client.bet(&player_a, &bet_token, &50);
+    env.budget().reset_unlimited();
+    assert_eq!(deployer_client.token_games(&bet_token, &0, &10), vec![&env]);
+
+    deployer_client.game(&contract_id);
+    assert_eq!(
+        deployer_client.token_games(&bet_token, &0, &10),
+        vec![&env, contract_id]
+    );
+}
+
+#[test]
+fn test_deploy_for() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b,
+        contract_id: _,
+        game_client,
+    } = GameTest::setup();
+
+    let wasm_hash = env.deployer().upload_contract_wasm(contract::WASM);
+    let mut config = game_client.config();
+    config.spectators = true;
+
+    assert_eq!(deployer_client.nonce(&player_a, &player_b), 0);
+    let predicted = deployer_client.predict_address(&player_a, &player_b, &0);
+    let game1 = deployer_client.deploy_for(&wasm_hash, &player_a, &player_b, &config);
+    assert_eq!(game1, predicted);
+    assert_eq!(deployer_client.nonce(&player_a, &player_b), 1);
+
+    let game1_client = contract::Client::new(&env, &game1);
+    assert_eq!(game1_client.player_a(), player_a);
+    assert!(game1_client.config().spectators);
+
+    let predicted = deployer_client.predict_address(&player_a, &player_b, &1);
+    env.budget().reset_unlimited();
+    let game2 = deployer_client.deploy_for(&wasm_hash, &player_a, &player_b, &config);
+    assert_eq!(game2, predicted);
+    assert_ne!(game1, game2);
+    assert_eq!(game1_client.config().series_game, 0);
+    assert_eq!(contract::Client::new(&env, &game2).config().series_game, 1);
+
+    assert_eq!(deployer_client.nonce(&player_b, &player_a), 0);
+    assert_ne!(
+        deployer_client.predict_address(&player_b, &player_a, &0),
+        game1
+    );
+}
+
+#[test]
+fn test_deploy_for_needs_players_auth() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b,
+        contract_id: _,
+        game_client,
+    } = GameTest::setup();
+
+    let wasm_hash = env.deployer().upload_contract_wasm(contract::WASM);
+    let config = game_client.config();
+    deployer_client.deploy_for(&wasm_hash, &player_a, &player_b, &config);
+
+    let auths = env.auths();
+    assert_eq!(auths.len(), 2);
+    assert_eq!(auths[0].0, player_a);
+    assert_eq!(auths[1].0, player_b);
+}
+
+#[test]
+fn test_deploy_for_alternate() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b,
+        contract_id: _,
+        game_client,
+    } = GameTest::setup();
+
+    let wasm_hash = env.deployer().upload_contract_wasm(contract::WASM);
+    let mut config = game_client.config();
+    config.start = contract::StartPolicy::Alternate;
+
+    for turn in [&player_a, &player_b, &player_a] {
+        env.budget().reset_unlimited();
+        let id = deployer_client.deploy_for(&wasm_hash, &player_a, &player_b, &config);
+        assert_eq!(contract::Client::new(&env, &id).turn(), *turn);
+    }
+}
+
+#[test]
+fn test_follow_feed() {
+    let game_test = GameTest::setup();
+    let env = &game_test.env;
+    let deployer_client = &game_test.deployer_client;
+    let watcher = Address::random(env);
+    assert_eq!(deployer_client.feed(&watcher, &0, &10), vec![env]);
+
+    deployer_client.follow(&watcher, &game_test.player_a);
+    deployer_client.follow(&watcher, &game_test.player_b);
+    assert_eq!(
+        deployer_client.following(&watcher),
+        vec![env, game_test.player_a.clone(), game_test.player_b.clone()]
+    );
+    // The game deployed in the setup
+    assert_eq!(
+        deployer_client.feed(&watcher, &0, &10),
+        vec![env, game_test.contract_id.clone()]
+    );
+
+    let other_watcher = Address::random(env);
+    deployer_client.follow(&other_watcher, &game_test.player_b);
+
+    // One event per watcher, even for a watcher following both players
+    let game1 = game_test.deploy_new_game(BytesN::from_array(env, &[1; 32]));
+    let events = env.events().all();
+    for follower in [&watcher, &other_watcher] {
+        let topics: Vec<Val> =
+            (Symbol::new(env, "game_for_followed"), follower.clone()).into_val(env);
+        let mut notified = events.iter().filter(|event| event.1 == topics);
+        let id: Address = notified.next().unwrap().2.into_val(env);
+        assert_eq!(id, game1.address);
+        assert!(notified.next().is_none());
+    }
+
+    env.budget().reset_unlimited();
+    let game2 = game_test.deploy_new_game(BytesN::from_array(env, &[2; 32]));
+    assert_eq!(
+        deployer_client.feed(&watcher, &0, &10),
+        vec![
+            env,
+            game2.address.clone(),
+            game1.address.clone(),
+            game_test.contract_id.clone()
+        ]
+    );
+    assert_eq!(
+        deployer_client.feed(&watcher, &1, &1),
+        vec![env, game1.address.clone()]
+    );
+
+    deployer_client.unfollow(&watcher, &game_test.player_a);
+    assert_eq!(
+        deployer_client.following(&watcher),
+        vec![env, game_test.player_b.clone()]
+    );
+    assert_eq!(
+        deployer_client.feed(&watcher, &0, &2),
+        vec![env, game2.address, game1.address]
+    );
+}
+
+#[test]
+fn test_feed_merges_players() {
+    let game_test = GameTest::setup();
+    let env = &game_test.env;
+    let deployer_client = &game_test.deployer_client;
+    let wasm_hash = env.deployer().upload_contract_wasm(contract::WASM);
+    let player_c = Address::random(env);
+    let player_d = Address::random(env);
+    let watcher = Address::random(env);
+    deployer_client.follow(&watcher, &player_c);
+    deployer_client.follow(&watcher, &player_d);
+
+    let mut games = vec![env];
+    for (i, player) in [&player_c, &player_d, &player_d, &player_c]
+        .iter()
+        .enumerate()
+    {
+        let salt = BytesN::from_array(env, &[i as u8 + 1; 32]);
+        let init_fn_args = ((*player).clone(), game_test.player_a.clone()).into_val(env);
+        games.push_front(deployer_client.deploy(&salt, &wasm_hash, &init_fn_args));
+    }
+
+    assert_eq!(deployer_client.feed(&watcher, &0, &10), games);
+    assert_eq!(deployer_client.feed(&watcher, &1, &2), games.slice(1..3));
+}
+
+#[test]
+#[should_panic(expected = "You follow too many players")]
+fn test_follow_cap() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a: _,
+        player_b: _,
+        contract_id: _,
+        game_client: _,
+    } = GameTest::setup();
+    let watcher = Address::random(&env);
+
+    for _ in 0..=20 {
+        deployer_client.follow(&watcher, &Address::random(&env));
+    }
+}
+
+#[test]
+#[should_panic(expected = "Player has too many followers")]
+fn test_followers_cap() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b: _,
+        contract_id: _,
+        game_client: _,
+    } = GameTest::setup();
+
+    for _ in 0..=50 {
+        deployer_client.follow(&Address::random(&env), &player_a);
+    }
+}
+
+#[test]
+fn test_profiles() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b,
+        contract_id,
+        game_client,
+    } = GameTest::setup();
+
+    let avatar = BytesN::from_array(&env, &[7; 32]);
+    let profile = deployer_client.set_profile(
+        &player_a,
+        &String::from_slice(&env, "Alice_1"),
+        &Some(avatar.clone()),
+        &None,
+    );
+    assert_eq!(deployer_client.profile(&player_a), Some(profile.clone()));
+    assert_eq!(
+        deployer_client.player_by_name(&String::from_slice(&env, "alice_1")),
+        Some(player_a.clone())
+    );
+
+    // Renaming frees the old name
+    deployer_client.set_profile(&player_a, &String::from_slice(&env, "Alice"), &None, &None);
+    deployer_client.set_profile(
+        &player_b,
+        &String::from_slice(&env, "alice_1"),
+        &None,
+        &None,
+    );
+    assert_eq!(
+        deployer_client.profile(&player_b).unwrap().avatar,
+        MaybeBytes32::None
+    );
+
+    GameTest::make_player_a_win(&game_client, player_a.clone(), player_b.clone());
+    env.budget().reset_unlimited();
+    deployer_client.game(&contract_id);
+
+    let leaderboard = deployer_client.leaderboard(&0, &10);
+    assert_eq!(leaderboard.len(), 1);
+    let entry = leaderboard.get(0).unwrap();
+    assert_eq!(entry.player, player_a);
+    assert_eq!(entry.score, 1);
+    assert_eq!(
+        entry.profile.to_option().unwrap().name,
+        String::from_slice(&env, "Alice")
+    );
+}
+
+#[test]
+#[should_panic(expected = "Name is already taken")]
+fn test_profile_name_taken() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b,
+        contract_id: _,
+        game_client: _,
+    } = GameTest::setup();
+
+    deployer_client.set_profile(&player_a, &String::from_slice(&env, "Alice"), &None, &None);
+    deployer_client.set_profile(&player_b, &String::from_slice(&env, "ALICE"), &None, &None);
+}
+
+#[test]
+#[should_panic(expected = "Name can only use letters, digits and _")]
+fn test_profile_name_chars() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b: _,
+        contract_id: _,
+        game_client: _,
+    } = GameTest::setup();
+
+    deployer_client.set_profile(&player_a, &String::from_slice(&env, "Al ice"), &None, &None);
+}
+
+#[test]
+fn test_ranking_cap() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b,
+        contract_id: _,
+        game_client: _,
+    } = GameTest::setup();
+    env.budget().reset_unlimited();
+
+    env.as_contract(&deployer_client.address, || {
+        ranking::update(&env, Board::AllTime, &player_a, 1);
+        for _ in 1..RANKING_CAP {
+            ranking::update(&env, Board::AllTime, &Address::random(&env), 2);
+        }
+        let ranks = ranking::get(&env, &Board::AllTime);
+        assert_eq!(ranks.len(), RANKING_CAP);
+        assert_eq!(ranks.last().unwrap().player, player_a);
+
+        // A full ranking drops its last player, and doesn't take lower scores
+        ranking::update(&env, Board::AllTime, &player_b, 2);
+        ranking::update(&env, Board::AllTime, &player_a, 1);
+        let ranks = ranking::get(&env, &Board::AllTime);
+        assert_eq!(ranks.len(), RANKING_CAP);
+        assert_eq!(ranks.last().unwrap().player, player_b);
+        assert!(ranks.iter().all(|rank| rank.player != player_a));
+
+        // A player moves up instead of being listed twice
+        ranking::update(&env, Board::AllTime, &player_b, 3);
+        let ranks = ranking::get(&env, &Board::AllTime);
+        assert_eq!(ranks.len(), RANKING_CAP);
+        assert_eq!(ranks.first().unwrap().player, player_b);
+        assert_eq!(
+            ranks.iter().filter(|rank| rank.player == player_b).count(),
+            1
+        );
+    });
+}
+
+#[test]
+fn test_trophy_milestone() {
+    let game_test = GameTest::setup();
+    let env = &game_test.env;
+    let deployer_client = &game_test.deployer_client;
+
+    let trophy_id = env.register_contract(None, tictactoe_trophy::TrophyContract);
+    let trophy_client = tictactoe_trophy::TrophyContractClient::new(env, &trophy_id);
+    let uris = map![env, (10, String::from_slice(env, "ipfs://ten"))];
+    trophy_client.init(&deployer_client.address, &uris);
+    deployer_client.set_admin(&Address::random(env));
+    deployer_client.set_trophy(&trophy_id);
+
+    for i in 1..=10 {
+        env.budget().reset_unlimited();
+        let game = game_test.deploy_new_game(BytesN::from_array(env, &[i; 32]));
+        GameTest::make_player_a_win(
+            &game,
+            game_test.player_a.clone(),
+            game_test.player_b.clone(),
+        );
+        deployer_client.game(&game.address);
+        if i == 9 {
+            assert_eq!(trophy_client.trophies(&game_test.player_a), vec![env]);
+        }
+    }
+
+    assert_eq!(trophy_client.trophies(&game_test.player_a), vec![env, 0]);
+    assert_eq!(trophy_client.trophy(&0).wins, 10);
+}
+
+#[test]
+fn test_set_trophy_requires_admin() {
+    let game_test = GameTest::setup();
+    let env = &game_test.env;
+    let deployer_client = &game_test.deployer_client;
+    let admin = Address::random(env);
+    deployer_client.set_admin(&admin);
+
+    let trophy_id = env.register_contract(None, tictactoe_trophy::TrophyContract);
+    let trophy_client = tictactoe_trophy::TrophyContractClient::new(env, &trophy_id);
+    trophy_client.init(&deployer_client.address, &map![env]);
+    deployer_client.set_trophy(&trophy_id);
+    let auths = env.auths();
+    assert_eq!(auths.len(), 1);
+    assert_eq!(auths[0].0, admin);
+    assert_eq!(deployer_client.trophy(), Some(trophy_id));
+}
+
+#[test]
+#[should_panic(expected = "The manager is not the trophy contract's admin")]
+fn test_set_trophy_other_admin() {
+    let game_test = GameTest::setup();
+    let env = &game_test.env;
+    let deployer_client = &game_test.deployer_client;
+    deployer_client.set_admin(&Address::random(env));
+
+    // Initialized by somebody else before the manager's admin could
+    let trophy_id = env.register_contract(None, tictactoe_trophy::TrophyContract);
+    let trophy_client = tictactoe_trophy::TrophyContractClient::new(env, &trophy_id);
+    trophy_client.init(&Address::random(env), &map![env]);
+
+    deployer_client.set_trophy(&trophy_id);
+}
+
+#[test]
+fn test_award_tournament() {
+    let game_test = GameTest::setup();
+    let env = &game_test.env;
+    let deployer_client = &game_test.deployer_client;
+    let admin = Address::random(env);
+    deployer_client.set_admin(&admin);
+
+    let trophy_id = env.register_contract(None, tictactoe_trophy::TrophyContract);
+    let trophy_client = tictactoe_trophy::TrophyContractClient::new(env, &trophy_id);
+    let uris = map![env, (0, String::from_slice(env, "ipfs://cup"))];
+    trophy_client.init(&deployer_client.address, &uris);
+    deployer_client.set_trophy(&trophy_id);
+
+    let id = deployer_client.award_tournament(&game_test.player_b);
+    let auths = env.auths();
+    assert_eq!(auths[0].0, admin);
+    assert_eq!(trophy_client.trophies(&game_test.player_b), vec![env, id]);
+    assert_eq!(trophy_client.trophy(&id).wins, 0);
+}
+
+#[test]
+fn test_failed_mint_does_not_block_game() {
+    let game_test = GameTest::setup();
+    let env = &game_test.env;
+    let deployer_client = &game_test.deployer_client;
+
+    // No metadata URI for the 10 wins milestone, so minting it fails
+    let trophy_id = env.register_contract(None, tictactoe_trophy::TrophyContract);
+    let trophy_client = tictactoe_trophy::TrophyContractClient::new(env, &trophy_id);
+    trophy_client.init(&deployer_client.address, &map![env]);
+    deployer_client.set_admin(&Address::random(env));
+    deployer_client.set_trophy(&trophy_id);
+
+    for i in 1..=10 {
+        env.budget().reset_unlimited();
+        let game = game_test.deploy_new_game(BytesN::from_array(env, &[i; 32]));
+        GameTest::make_player_a_win(
+            &game,
+            game_test.player_a.clone(),
+            game_test.player_b.clone(),
+        );
+        deployer_client.game(&game.address);
+    }
+
+    assert_eq!(
+        deployer_client.scores().get(game_test.player_a.clone()),
+        Some(10)
+    );
+    assert_eq!(trophy_client.trophies(&game_test.player_a), vec![env]);
+}
+
+#[test]
+fn test_badges_win() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b,
+        contract_id,
+        game_client,
+    } = GameTest::setup();
+
+    // player_b holds the centre
+    GameTest::make_player_a_win(&game_client, player_a.clone(), player_b.clone());
+    env.budget().reset_unlimited();
+    deployer_client.game(&contract_id);
+
+    assert_eq!(
+        deployer_client.badges(&player_a),
+        vec![&env, Badge::FirstWin, Badge::FastestWin]
+    );
+    assert_eq!(deployer_client.badges(&player_b), vec![&env]);
+}
+
+#[test]
+fn test_badges_fastest_win_ended_at() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b,
+        contract_id,
+        game_client,
+    } = GameTest::setup();
+
+    // Won right away, but the manager only sees it later
+    GameTest::make_player_a_win(&game_client, player_a.clone(), player_b.clone());
+    env.ledger().with_mut(|li| li.timestamp += 1000);
+    env.budget().reset_unlimited();
+    deployer_client.game(&contract_id);
+
+    // Won after 10 seconds and seen right away, slower than the first game
+    let wasm_hash = env.deployer().upload_contract_wasm(contract::WASM);
+    let salt = BytesN::from_array(&env, &[1; 32]);
+    let init_fn_args = (player_b.clone(), player_a.clone()).into_val(&env);
+    let id = deployer_client.deploy(&salt, &wasm_hash, &init_fn_args);
+    env.ledger().with_mut(|li| li.timestamp += 10);
+    let client = contract::Client::new(&env, &id);
+    GameTest::make_player_a_win(&client, player_b.clone(), player_a.clone());
+    env.budget().reset_unlimited();
+    deployer_client.game(&id);
+
+    assert!(deployer_client
+        .badges(&player_a)
+        .contains(Badge::FastestWin));
+    assert!(!deployer_client
+        .badges(&player_b)
+        .contains(Badge::FastestWin));
+}
+
+#[test]
+fn test_badges_centre_hold() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b,
+        contract_id,
+        game_client,
+    } = GameTest::setup();
+
+    game_client.play(&player_a, &1, &1);
+    game_client.play(&player_b, &0, &1);
+    game_client.play(&player_a, &0, &0);
+    game_client.play(&player_b, &1, &0);
+    game_client.play(&player_a, &2, &2);
+    env.budget().reset_unlimited();
+    deployer_client.game(&contract_id);
+
+    assert!(deployer_client
+        .badges(&player_a)
+        .contains(Badge::CentreHold));
+}
+
+#[test]
+fn test_badges_centre_hold_classic_only() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b,
+        contract_id: _,
+        game_client,
+    } = GameTest::setup();
+
+    // Either player can place either mark, so the grid doesn't tell whose they are
+    let wasm_hash = env.deployer().upload_contract_wasm(contract::WASM);
+    let mut config = game_client.config();
+    config.rules = contract::Rules::Wild;
+    let id = deployer_client.deploy_for(&wasm_hash, &player_a, &player_b, &config);
+    let client = contract::Client::new(&env, &id);
+    client.play_mark(&player_a, &0, &0, &contract::Mark::O);
+    client.play_mark(&player_b, &2, &2, &contract::Mark::X);
+    client.play_mark(&player_a, &1, &0, &contract::Mark::O);
+    client.play_mark(&player_b, &2, &0, &contract::Mark::O);
+    env.budget().reset_unlimited();
+    deployer_client.game(&id);
+
+    assert!(deployer_client.badges(&player_b).contains(Badge::FirstWin));
+    assert!(!deployer_client
+        .badges(&player_b)
+        .contains(Badge::CentreHold));
+}
+
+#[test]
+fn test_badges_perfect_defence() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b,
+        contract_id,
+        game_client,
+    } = GameTest::setup();
+
+    game_client.play(&player_a, &0, &0);
+    game_client.play(&player_b, &1, &0);
+    game_client.play(&player_a, &2, &0);
+    game_client.play(&player_b, &1, &1);
+    game_client.play(&player_a, &1, &2);
+    game_client.play(&player_b, &2, &1);
+    game_client.play(&player_a, &0, &1);
+    game_client.play(&player_b, &0, &2);
+    game_client.play(&player_a, &2, &2);
+    env.budget().reset_unlimited();
+    deployer_client.game(&contract_id);
+
+    assert_eq!(
+        deployer_client.badges(&player_a),
+        vec![&env, Badge::PerfectDefence]
+    );
+    assert_eq!(
+        deployer_client.badges(&player_b),
+        vec![&env, Badge::PerfectDefence]
+    );
+}
+
+#[test]
+fn test_badges_win_streak() {
+    let game_test = GameTest::setup();
+    let env = &game_test.env;
+
+    for i in 1..=5 {
+        env.budget().reset_unlimited();
+        let game = game_test.deploy_new_game(BytesN::from_array(env, &[i; 32]));
+        GameTest::make_player_a_win(
+            &game,
+            game_test.player_a.clone(),
+            game_test.player_b.clone(),
+        );
+        game_test.deployer_client.game(&game.address);
+    }
+
+    let badges = game_test.deployer_client.badges(&game_test.player_a);
+    assert!(badges.contains(Badge::WinStreak));
+}
+
+#[test]
+fn test_head_to_head_and_streak() {
+    let game_test = GameTest::setup();
+    let env = &game_test.env;
+    let deployer_client = &game_test.deployer_client;
+    let player_a = &game_test.player_a;
+    let player_b = &game_test.player_b;
+
+    for i in 1..=3 {
+        env.budget().reset_unlimited();
+        let game = game_test.deploy_new_game(BytesN::from_array(env, &[i; 32]));
+        GameTest::make_player_a_win(&game, player_a.clone(), player_b.clone());
+        deployer_client.game(&game.address);
+    }
+    game_test.game_client.resign(player_a);
+    env.budget().reset_unlimited();
+    deployer_client.game(&game_test.contract_id);
+
+    let record = crate::stats::Record {
+        wins: 3,
+        draws: 0,
+        losses: 1,
+    };
+    assert_eq!(deployer_client.head_to_head(player_a, player_b), record);
+    let record = crate::stats::Record {
+        wins: 1,
+        draws: 0,
+        losses: 3,
+    };
+    assert_eq!(deployer_client.head_to_head(player_b, player_a), record);
+
+    let streak = crate::stats::Streak {
+        current: 0,
+        best: 3,
+    };
+    assert_eq!(deployer_client.streak(player_a), streak);
+    assert_eq!(deployer_client.streak(player_b).current, 1);
+}
+
+#[test]
+fn test_season() {
+    let game_test = GameTest::setup();
+    let env = &game_test.env;
+    let deployer_client = &game_test.deployer_client;
+    let player_a = &game_test.player_a;
+    let player_b = &game_test.player_b;
+
+    let admin = Address::random(env);
+    let prize_token = env.register_stellar_asset_contract(Address::random(env));
+    let token_client = token::Client::new(env, &prize_token);
+    token::AdminClient::new(env, &prize_token).mint(&admin, &101);
+
+    deployer_client.set_admin(&admin);
+    let id = deployer_client.start_season(&0, &100, &Some(prize_token.clone()), &101, &2);
+    assert_eq!(id, 0);
+    assert_eq!(token_client.balance(&admin), 0);
+
+    for i in 1..=2 {
+        env.budget().reset_unlimited();
+        let game = game_test.deploy_new_game(BytesN::from_array(env, &[i; 32]));
+        GameTest::make_player_a_win(&game, player_a.clone(), player_b.clone());
+        deployer_client.game(&game.address);
+    }
+    game_test.game_client.resign(player_a);
+    env.budget().reset_unlimited();
+    deployer_client.game(&game_test.contract_id);
+
+    let live = deployer_client.season_standings(&0, &0, &10);
+    assert_eq!(live.get(0).unwrap().player, player_a.clone());
+    assert_eq!(live.get(0).unwrap().score, 2);
+
+    env.ledger().with_mut(|li| li.sequence_number = 101);
+    let standings = deployer_client.close_season();
+    assert_eq!(standings.len(), 2);
+    assert_eq!(standings.get(1).unwrap().player, player_b.clone());
+    assert_eq!(token_client.balance(player_a), 51);
+    assert_eq!(token_client.balance(player_b), 50);
+
+    // Wins after the season don't count, and the archive doesn't change
+    env.budget().reset_unlimited();
+    let game = game_test.deploy_new_game(BytesN::from_array(env, &[3; 32]));
+    GameTest::make_player_a_win(&game, player_a.clone(), player_b.clone());
+    deployer_client.game(&game.address);
+    assert_eq!(deployer_client.season_standings(&0, &0, &10), standings);
+    assert!(deployer_client.season(&0).closed);
+
+    let id = deployer_client.start_season(&200, &300, &None, &0, &0);
+    assert_eq!(id, 1);
+}
+
+#[test]
+fn test_season_win_seen_late() {
+    let game_test = GameTest::setup();
+    let env = &game_test.env;
+    let deployer_client = &game_test.deployer_client;
+    let player_a = &game_test.player_a;
+    let player_b = &game_test.player_b;
+
+    env.ledger().with_mut(|li| li.sequence_number = 50);
+    let early = game_test.deploy_new_game(BytesN::from_array(env, &[1; 32]));
+    GameTest::make_player_a_win(&early, player_a.clone(), player_b.clone());
+
+    deployer_client.set_admin(&Address::random(env));
+    deployer_client.start_season(&100, &200, &None, &0, &0);
+
+    // Won on the last ledger of the season, seen by `game` after it
+    env.ledger().with_mut(|li| li.sequence_number = 200);
+    GameTest::make_player_a_win(&game_test.game_client, player_a.clone(), player_b.clone());
+    env.ledger().with_mut(|li| li.sequence_number = 250);
+    env.budget().reset_unlimited();
+    deployer_client.game(&game_test.contract_id);
+    // Won before the season started
+    deployer_client.game(&early.address);
+
+    let standings = deployer_client.close_season();
+    assert_eq!(standings.len(), 1);
+    assert_eq!(standings.get(0).unwrap().player, player_a.clone());
+    assert_eq!(standings.get(0).unwrap().score, 1);
+    assert_eq!(deployer_client.scores().get(player_a.clone()), Some(2));
+}
+
+#[test]
+#[should_panic(expected = "Only the deployer can initialize")]
+fn test_init_not_deployer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let deployer_client = DeployerClient::new(&env, &env.register_contract(None, Deployer));
+
+    let salt = BytesN::from_array(&env, &[0; 32]);
+    deployer_client.init(&Address::random(&env), &salt);
+}
+
+#[test]
+#[should_panic(expected = "Already initialized")]
+fn test_init_twice() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a: _,
+        player_b: _,
+        contract_id: _,
+        game_client: _,
+    } = GameTest::setup();
+
+    let salt = BytesN::from_array(&env, &[0; 32]);
+    deployer_client.init(&Address::random(&env), &salt);
+}
+
+#[test]
+fn test_set_admin_needs_admin() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a: _,
+        player_b: _,
+        contract_id: _,
+        game_client: _,
+    } = GameTest::setup();
+
+    let admin = Address::random(&env);
+    deployer_client.set_admin(&admin);
+    deployer_client.set_admin(&Address::random(&env));
+
+    let auths = env.auths();
+    assert_eq!(auths.len(), 1);
+    assert_eq!(auths[0].0, admin);
+}
+
+#[test]
+#[should_panic(expected = "Season has not ended")]
+fn test_season_close_early() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a: _,
+        player_b: _,
+        contract_id: _,
+        game_client: _,
+    } = GameTest::setup();
+
+    deployer_client.set_admin(&Address::random(&env));
+    deployer_client.start_season(&0, &100, &None, &0, &0);
+    deployer_client.close_season();
+}
+
+#[test]
+fn test_expired_game_scores() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b,
+        contract_id: _,
+        game_client,
+    } = GameTest::setup();
+
+    let wasm_hash = env.deployer().upload_contract_wasm(contract::WASM);
+    let mut config = game_client.config();
+    config.on_expiry = contract::ExpiryPolicy::OnMove;
+    let id = deployer_client.deploy_for(&wasm_hash, &player_a, &player_b, &config);
+    let client = contract::Client::new(&env, &id);
+    client.play(&player_a, &1, &1);
+
+    // player_b stalls until the game expires
+    env.ledger().with_mut(|li| li.timestamp += 60 * 10);
+    env.budget().reset_unlimited();
+    assert!(deployer_client.game(&id).ended);
+
+    assert_eq!(client.status(), contract::GameStatus::Expired);
+    assert_eq!(deployer_client.scores(), map![&env, (player_a.clone(), 1)]);
+    assert_eq!(deployer_client.head_to_head(&player_b, &player_a).losses, 1);
+}
+
+#[test]
+fn test_expired_game_solver_budget() {
+    let GameTest {
+        env,
+        deployer_client,
+        player_a,
+        player_b,
+        contract_id: _,
+        game_client,
+    } = GameTest::setup();
+    let wasm_hash = env.deployer().upload_contract_wasm(contract::WASM);
+
+    // `game` calls the game contract a few times, each instantiating it, so
+    // the solver's cost is compared with a policy that doesn't search
+    let expire_cost = |on_expiry: contract::ExpiryPolicy| {
+        let mut config = game_client.config();
+        config.on_expiry = on_expiry;
+        let id = deployer_client.deploy_for(&wasm_hash, &player_a, &player_b, &config);
+        let client = contract::Client::new(&env, &id);
+        client.play(&player_a, &0, &0);
+        client.play(&player_b, &1, &0);
+
+        env.ledger().with_mut(|li| li.timestamp += 60 * 10);
+        env.budget().reset_unlimited();
+        assert!(deployer_client.game(&id).ended);
+        env.budget().cpu_instruction_cost()
+    };
+    let on_move = expire_cost(contract::ExpiryPolicy::OnMove);
+    let solver = expire_cost(contract::ExpiryPolicy::Solver);
+
+    // Solved from the opening for a fraction of the default budget
+    assert!(solver < on_move + 4_000_000);
+    assert_eq!(
+        deployer_client.scores(),
+        map![&env, (player_a, 1), (player_b, 1)]
+    );
+}
@@ -0,0 +1,49 @@
+use crate::DataKey;
+use soroban_sdk::{contractclient, Address, Env};
+
+// Wins after which a player gets a trophy
+pub const MILESTONES: [u32; 2] = [10, 100];
+
+// Milestone of the trophies the admin awards for tournament wins
+pub const TOURNAMENT: u32 = 0;
+
+#[contractclient(name = "TrophyClient")]
+pub trait TrophyInterface {
+    fn mint(env: Env, to: Address, wins: u32) -> u32;
+    fn admin(env: Env) -> Address;
+}
+
+// The trophy contract must be initialized with the manager as its admin. Anyone
+// can init a trophy contract first, so one with another admin is refused.
+pub fn set(env: &Env, trophy: Address) {
+    assert!(
+        !env.storage().instance().has(&DataKey::Trophy),
+        "Trophy contract is already set"
+    );
+    assert!(
+        TrophyClient::new(env, &trophy).admin() == env.current_contract_address(),
+        "The manager is not the trophy contract's admin"
+    );
+    env.storage().instance().set(&DataKey::Trophy, &trophy);
+}
+
+pub fn get(env: &Env) -> Option<Address> {
+    env.storage().instance().get(&DataKey::Trophy)
+}
+
+// Unlike milestones, a failing mint fails the call so the admin sees it
+pub fn award_tournament(env: &Env, player: &Address) -> u32 {
+    let trophy = get(env).expect("Trophy contract is not set");
+    TrophyClient::new(env, &trophy).mint(player, &TOURNAMENT)
+}
+
+// Mints a trophy if `wins` is a milestone and a trophy contract is set.
+// A failing mint is ignored so it can't block recording the game.
+pub fn award(env: &Env, player: &Address, wins: u32) {
+    if !MILESTONES.contains(&wins) {
+        return;
+    }
+    if let Some(trophy) = get(env) {
+        let _ = TrophyClient::new(env, &trophy).try_mint(player, &wins);
+    }
+}
@@ -0,0 +1,149 @@
+#![no_std]
+use soroban_sdk::{contract, contractimpl, contracttype, vec, Address, Env, Map, String, Vec};
+
+const DAY_IN_LEDGERS: u32 = 17280;
+const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Trophy {
+    pub owner: Address,
+    // Wins milestone the trophy was awarded for
+    pub wins: u32,
+}
+
+#[contracttype]
+pub enum DataKey {
+    Admin,
+    // Metadata URI of each milestone
+    Uris,
+    NextId,
+    Trophy(u32),
+    Owned(Address),
+}
+
+#[contract]
+pub struct TrophyContract;
+
+#[contractimpl]
+impl TrophyContract {
+    // `admin` is the only one allowed to mint, usually the manager contract
+    pub fn init(env: Env, admin: Address, uris: Map<u32, String>) {
+        assert!(
+            !env.storage().instance().has(&DataKey::Admin),
+            "Already initialized"
+        );
+        bump_instance(&env);
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::Uris, &uris);
+    }
+
+    pub fn mint(env: Env, to: Address, wins: u32) -> u32 {
+        get_admin(&env).require_auth();
+        bump_instance(&env);
+        assert!(
+            get_uris(&env).contains_key(wins),
+            "No trophy for this milestone"
+        );
+
+        let id = env.storage().instance().get(&DataKey::NextId).unwrap_or(0);
+        env.storage().instance().set(&DataKey::NextId, &(id + 1));
+        set_trophy(&env, id, &Trophy { owner: to, wins });
+        add_owned(&env, id);
+        id
+    }
+
+    pub fn transfer(env: Env, from: Address, to: Address, id: u32) {
+        from.require_auth();
+        bump_instance(&env);
+        let mut trophy = get_trophy(&env, id);
+        assert!(trophy.owner == from, "You don't own this trophy");
+
+        remove_owned(&env, id, &from);
+        trophy.owner = to;
+        set_trophy(&env, id, &trophy);
+        add_owned(&env, id);
+    }
+
+    pub fn admin(env: Env) -> Address {
+        get_admin(&env)
+    }
+
+    pub fn trophy(env: Env, id: u32) -> Trophy {
+        get_trophy(&env, id)
+    }
+
+    pub fn owner(env: Env, id: u32) -> Address {
+        get_trophy(&env, id).owner
+    }
+
+    pub fn uri(env: Env, id: u32) -> String {
+        let trophy = get_trophy(&env, id);
+        get_uris(&env).get(trophy.wins).unwrap()
+    }
+
+    pub fn trophies(env: Env, owner: Address) -> Vec<u32> {
+        get_owned(&env, &owner)
+    }
+}
+
+fn bump_instance(env: &Env) {
+    env.storage().instance().bump(INSTANCE_BUMP_AMOUNT);
+}
+
+fn get_admin(env: &Env) -> Address {
+    env.storage()
+        .instance()
+        .get(&DataKey::Admin)
+        .expect("Not initialized")
+}
+
+fn get_uris(env: &Env) -> Map<u32, String> {
+    env.storage().instance().get(&DataKey::Uris).unwrap()
+}
+
+fn get_trophy(env: &Env, id: u32) -> Trophy {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Trophy(id))
+        .expect("Trophy doesn't exist")
+}
+
+fn set_trophy(env: &Env, id: u32, trophy: &Trophy) {
+    let key = DataKey::Trophy(id);
+    env.storage().persistent().set(&key, trophy);
+    env.storage()
+        .persistent()
+        .bump(&key, PERSISTENT_BUMP_AMOUNT);
+}
+
+fn get_owned(env: &Env, owner: &Address) -> Vec<u32> {
+    let key = DataKey::Owned(owner.clone());
+    env.storage().persistent().get(&key).unwrap_or(vec![env])
+}
+
+fn set_owned(env: &Env, owner: &Address, ids: &Vec<u32>) {
+    let key = DataKey::Owned(owner.clone());
+    env.storage().persistent().set(&key, ids);
+    env.storage()
+        .persistent()
+        .bump(&key, PERSISTENT_BUMP_AMOUNT);
+}
+
+fn add_owned(env: &Env, id: u32) {
+    let owner = get_trophy(env, id).owner;
+    let mut ids = get_owned(env, &owner);
+    ids.push_back(id);
+    set_owned(env, &owner, &ids);
+}
+
+fn remove_owned(env: &Env, id: u32, owner: &Address) {
+    let mut ids = get_owned(env, owner);
+    if let Some(index) = ids.first_index_of(id) {
+        ids.remove(index);
+    }
+    set_owned(env, owner, &ids);
+}
+
+mod test;
@@ -0,0 +1,83 @@
+#![cfg(test)]
+
+use super::{Trophy, TrophyContract, TrophyContractClient};
+use soroban_sdk::{map, testutils::Address as _, vec, Address, Env, String};
+
+fn setup<'a>(env: &Env, admin: &Address) -> TrophyContractClient<'a> {
+    env.mock_all_auths();
+    let client = TrophyContractClient::new(env, &env.register_contract(None, TrophyContract));
+    let uris = map![
+        env,
+        (10, String::from_slice(env, "ipfs://ten")),
+        (100, String::from_slice(env, "ipfs://hundred"))
+    ];
+    client.init(admin, &uris);
+    client
+}
+
+#[test]
+fn test_mint() {
+    let env = Env::default();
+    let admin = Address::random(&env);
+    let player = Address::random(&env);
+    let client = setup(&env, &admin);
+
+    assert_eq!(client.mint(&player, &10), 0);
+    assert_eq!(client.mint(&player, &100), 1);
+
+    let trophy = Trophy {
+        owner: player.clone(),
+        wins: 100,
+    };
+    assert_eq!(client.trophy(&1), trophy);
+    assert_eq!(client.uri(&0), String::from_slice(&env, "ipfs://ten"));
+    assert_eq!(client.trophies(&player), vec![&env, 0, 1]);
+}
+
+#[test]
+#[should_panic(expected = "No trophy for this milestone")]
+fn test_mint_unknown_milestone() {
+    let env = Env::default();
+    let admin = Address::random(&env);
+    let client = setup(&env, &admin);
+
+    client.mint(&Address::random(&env), &5);
+}
+
+#[test]
+fn test_transfer() {
+    let env = Env::default();
+    let admin = Address::random(&env);
+    let player = Address::random(&env);
+    let friend = Address::random(&env);
+    let client = setup(&env, &admin);
+
+    client.mint(&player, &10);
+    client.transfer(&player, &friend, &0);
+
+    assert_eq!(client.owner(&0), friend);
+    assert_eq!(client.trophies(&player), vec![&env]);
+    assert_eq!(client.trophies(&friend), vec![&env, 0]);
+}
+
+#[test]
+#[should_panic(expected = "You don't own this trophy")]
+fn test_transfer_not_owner() {
+    let env = Env::default();
+    let admin = Address::random(&env);
+    let player = Address::random(&env);
+    let friend = Address::random(&env);
+    let client = setup(&env, &admin);
+
+    client.mint(&player, &10);
+    client.transfer(&friend, &friend, &0);
+}
+
+#[test]
+fn test_admin() {
+    let env = Env::default();
+    let admin = Address::random(&env);
+    let client = setup(&env, &admin);
+
+    assert_eq!(client.admin(), admin);
+}